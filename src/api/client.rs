@@ -0,0 +1,374 @@
+//! Typed HTTP client for the API, shared by the native and wasm frontends.
+
+use crate::api::v1::details::maps::{ListDetailsMapReq, ListDetailsMapRes};
+use crate::api::v1::details::players::{ListDetailsPlayerReq, ListDetailsPlayerRes};
+use crate::api::v1::details::regions::{ListDetailsRegionReq, ListDetailsRegionRes};
+use crate::api::v1::details::replays::{ListDetailsReplayReq, ListDetailsReplayRes};
+use crate::api::v1::details::teams::{ListDetailsTeamReq, ListDetailsTeamRes};
+use crate::api::v1::details::versions::{ListDetailsVersionReq, ListDetailsVersionRes};
+use crate::api::v1::details::vs_ai::{ListDetailsVsAiReq, ListDetailsVsAiRes};
+use crate::api::v1::search::{SearchReq, SearchRes};
+use crate::api::v1::snapshot_stats::SnapshotStats;
+use crate::api::v1::sql::{SqlQueryReq, SqlQueryRes};
+use crate::api::validation::FieldErrors;
+use crate::meta::{ResponseMeta, ResponseStatus};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// The maximum number of responses kept by the `EtagCache`
+const MAX_ETAG_ENTRIES: usize = 64;
+
+/// The errors returned by the API client
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// The request could not be sent or the connection failed
+    Http(String),
+    /// The request parameters could not be serialized
+    Request(String),
+    /// The server returned an error
+    Server {
+        /// The HTTP status code
+        status: u16,
+        /// The machine readable code of the `ResponseStatus::Error`
+        code: String,
+        /// The message of the `ResponseStatus::Error`
+        message: String,
+        /// The field level validation messages
+        fields: FieldErrors,
+    },
+    /// The response could not be decoded
+    Decode(String),
+    /// The request was replaced by a newer one before it completed
+    Cancelled,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Http(message) => write!(f, "HTTP Error: {}", message),
+            Self::Request(message) => write!(f, "Request Error: {}", message),
+            Self::Server {
+                status,
+                code,
+                message,
+                ..
+            } => write!(f, "Server Error ({} {}): {}", status, code, message),
+            Self::Decode(message) => write!(f, "Decode Error: {}", message),
+            Self::Cancelled => write!(f, "Request cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ApiError {
+    /// The field level validation messages returned by the server
+    pub fn field_errors(&self) -> Option<&FieldErrors> {
+        match self {
+            Self::Server { fields, .. } if !fields.is_empty() => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// A hint of what to check for the known server error codes
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Http(_) => Some("Check the backend is running and reachable"),
+            Self::Server { code, .. } => match code.as_str() {
+                "bad_request" | "invalid_params" | "unprocessable" => {
+                    Some("Check the filter values")
+                }
+                "not_found" => Some("Check the snapshot directory contains the expected files"),
+                "schema_mismatch" => Some(
+                    "The snapshot may have been written by an incompatible s2protocol version, regenerate it",
+                ),
+                "unavailable" => Some("The server is busy or the query took too long, try again"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Used to extract the error message of any response.
+#[derive(Deserialize)]
+struct MetaOnly {
+    meta: ResponseMeta,
+}
+
+/// The last response body of each URL with its ETag, shared by the clones of the client so
+/// that the results the server reports unchanged are not downloaded again.
+#[derive(Debug, Clone, Default)]
+pub struct EtagCache(Arc<Mutex<EtagEntries>>);
+
+#[derive(Debug, Default)]
+struct EtagEntries {
+    /// The last use, the ETag and the body of each URL
+    responses: HashMap<String, (u64, String, Vec<u8>)>,
+    next_use: u64,
+}
+
+impl EtagEntries {
+    fn next_use(&mut self) -> u64 {
+        let next_use = self.next_use;
+        self.next_use += 1;
+        next_use
+    }
+}
+
+impl EtagCache {
+    /// The ETag and the body of the last response of the URL
+    pub fn get(&self, url: &str) -> Option<(String, Vec<u8>)> {
+        let mut entries = self.0.lock().unwrap();
+        let next_use = entries.next_use();
+        let (last_use, etag, body) = entries.responses.get_mut(url)?;
+        *last_use = next_use;
+        Some((etag.clone(), body.clone()))
+    }
+
+    /// Keeps the body of a response, the least recently used URL is dropped when the cache
+    /// is full
+    pub fn insert(&self, url: String, etag: String, body: Vec<u8>) {
+        let mut entries = self.0.lock().unwrap();
+        if entries.responses.len() >= MAX_ETAG_ENTRIES && !entries.responses.contains_key(&url) {
+            if let Some(evicted) = entries
+                .responses
+                .iter()
+                .min_by_key(|(_, (last_use, _, _))| *last_use)
+                .map(|(url, _)| url.clone())
+            {
+                entries.responses.remove(&evicted);
+            }
+        }
+        let next_use = entries.next_use();
+        entries.responses.insert(url, (next_use, etag, body));
+    }
+}
+
+impl PartialEq for EtagCache {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A client for the API, one method per endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiClient {
+    /// The scheme, host and port of the backend, i.e. `http://localhost:3000`.
+    /// When empty, the requests are relative to the current page, which is what
+    /// the wasm frontend served by the backend needs.
+    pub base_url: String,
+    /// The responses of the GET requests, revalidated with `If-None-Match`
+    pub etags: EtagCache,
+}
+
+impl ApiClient {
+    /// Creates a new ApiClient
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            etags: EtagCache::default(),
+        }
+    }
+
+    /// Builds the URL of an API path, the path should start with `/`
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/api{}", self.base_url, path)
+    }
+
+    /// Builds the URL of an API path with the serialized query parameters
+    pub fn query_url<Q: Serialize>(&self, path: &str, query: &Q) -> Result<String, ApiError> {
+        let query =
+            serde_urlencoded::to_string(query).map_err(|e| ApiError::Request(e.to_string()))?;
+        Ok(format!("{}?{}", self.url(path), query))
+    }
+
+    /// Sends a GET request, the body of the previous response is reused when the server
+    /// answers `304 Not Modified`.
+    async fn get<T: DeserializeOwned>(&self, url: String) -> Result<T, ApiError> {
+        let mut request = ehttp::Request::get(&url);
+        request.headers = ehttp::Headers::new(&[("Accept", crate::common::JSON_MIME)]);
+        let cached = self.etags.get(&url);
+        if let Some((etag, _)) = &cached {
+            request.headers.insert("If-None-Match", etag);
+        }
+        let mut response = ehttp::fetch_async(request).await.map_err(ApiError::Http)?;
+        match cached {
+            Some((_, body)) if response.status == 304 => {
+                response.ok = true;
+                response.status = 200;
+                response.bytes = body;
+            }
+            _ if response.ok => {
+                if let Some(etag) = response.headers.get("etag") {
+                    self.etags
+                        .insert(url, etag.to_string(), response.bytes.clone());
+                }
+            }
+            _ => {}
+        }
+        Self::decode(response)
+    }
+
+    /// Sends a POST request with a JSON body
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, ApiError> {
+        let body = serde_json::to_vec(body).map_err(|e| ApiError::Request(e.to_string()))?;
+        let mut request = ehttp::Request::post(self.url(path), body);
+        request.headers = ehttp::Headers::new(&[
+            ("Accept", crate::common::JSON_MIME),
+            ("Content-Type", crate::common::JSON_MIME),
+        ]);
+        Self::fetch(request).await
+    }
+
+    /// Sends the request and decodes the response
+    async fn fetch<T: DeserializeOwned>(request: ehttp::Request) -> Result<T, ApiError> {
+        let response = ehttp::fetch_async(request).await.map_err(ApiError::Http)?;
+        Self::decode(response)
+    }
+
+    /// Decodes the response, the server error messages are extracted from the `meta` of the
+    /// response.
+    fn decode<T: DeserializeOwned>(response: ehttp::Response) -> Result<T, ApiError> {
+        if let Ok(MetaOnly {
+            meta:
+                ResponseMeta {
+                    status:
+                        ResponseStatus::Error {
+                            message,
+                            code,
+                            fields,
+                        },
+                    ..
+                },
+        }) = serde_json::from_slice(&response.bytes)
+        {
+            return Err(ApiError::Server {
+                status: response.status,
+                code,
+                message,
+                fields,
+            });
+        }
+        if !response.ok {
+            return Err(ApiError::Server {
+                status: response.status,
+                code: String::new(),
+                fields: FieldErrors::new(),
+                message: response
+                    .text()
+                    .filter(|text| !text.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| response.status_text.clone()),
+            });
+        }
+        serde_json::from_slice(&response.bytes).map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    /// Gets the snapshot metadata
+    pub async fn snapshot_stats(&self) -> Result<SnapshotStats, ApiError> {
+        self.get(self.url("/v1/snapshot_stats")).await
+    }
+
+    /// Gets the map frequency for the filters
+    pub async fn details_maps(
+        &self,
+        req: &ListDetailsMapReq,
+    ) -> Result<ListDetailsMapRes, ApiError> {
+        self.get(self.query_url("/v1/details/maps", req)?).await
+    }
+
+    /// Gets the player frequency for the filters
+    pub async fn details_players(
+        &self,
+        req: &ListDetailsPlayerReq,
+    ) -> Result<ListDetailsPlayerRes, ApiError> {
+        self.get(self.query_url("/v1/details/players", req)?).await
+    }
+
+    /// Gets the games, players and maps per region and month for the filters
+    pub async fn details_regions(
+        &self,
+        req: &ListDetailsRegionReq,
+    ) -> Result<ListDetailsRegionRes, ApiError> {
+        self.get(self.query_url("/v1/details/regions", req)?).await
+    }
+
+    /// Gets the replays matching the filters, the latest first
+    pub async fn details_replays(
+        &self,
+        req: &ListDetailsReplayReq,
+    ) -> Result<ListDetailsReplayRes, ApiError> {
+        self.get(self.query_url("/v1/details/replays", req)?).await
+    }
+
+    /// Gets the team frequency for the filters
+    pub async fn details_teams(
+        &self,
+        req: &ListDetailsTeamReq,
+    ) -> Result<ListDetailsTeamRes, ApiError> {
+        self.get(self.query_url("/v1/details/teams", req)?).await
+    }
+
+    /// Gets the game versions of the replays for the filters
+    pub async fn details_versions(
+        &self,
+        req: &ListDetailsVersionReq,
+    ) -> Result<ListDetailsVersionRes, ApiError> {
+        self.get(self.query_url("/v1/details/versions", req)?).await
+    }
+
+    /// Gets the results of the players against the A.I. for the filters
+    pub async fn details_vs_ai(
+        &self,
+        req: &ListDetailsVsAiReq,
+    ) -> Result<ListDetailsVsAiRes, ApiError> {
+        self.get(self.query_url("/v1/details/vs_ai", req)?).await
+    }
+
+    /// Gets the players, maps and replays matching a search
+    pub async fn search(&self, req: &SearchReq) -> Result<SearchRes, ApiError> {
+        self.get(self.query_url("/v1/search", req)?).await
+    }
+
+    /// Runs a read-only SQL query
+    pub async fn sql(&self, req: &SqlQueryReq) -> Result<SqlQueryRes, ApiError> {
+        self.post("/v1/sql", req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_client_url() {
+        assert_eq!(
+            ApiClient::default().url("/v1/details/maps"),
+            "/api/v1/details/maps"
+        );
+        assert_eq!(
+            ApiClient::new("http://localhost:3000/").url("/v1/sql"),
+            "http://localhost:3000/api/v1/sql"
+        );
+    }
+
+    #[test]
+    fn test_etag_cache_evicts_least_recently_used() {
+        let cache = EtagCache::default();
+        for i in 0..MAX_ETAG_ENTRIES {
+            cache.insert(format!("/{}", i), format!("\"{}\"", i), vec![]);
+        }
+        // Revalidating the first URL keeps it over the second one.
+        assert!(cache.get("/0").is_some());
+        cache.insert("/new".to_string(), "\"new\"".to_string(), vec![]);
+        assert!(cache.get("/0").is_some());
+        assert!(cache.get("/1").is_none());
+        assert!(cache.get("/new").is_some());
+    }
+}
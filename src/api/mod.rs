@@ -1,14 +1,48 @@
 //! API versions
 
+pub mod client;
+pub mod pending;
 pub mod v1;
+pub mod validation;
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::server::AppState;
 
 #[cfg(not(target_arch = "wasm32"))]
-use axum::{extract::State, Router};
+use axum::{extract::State, http::Uri, Router};
 
 #[cfg(not(target_arch = "wasm32"))]
 pub fn routes(shared_state: State<AppState>) -> Router {
-    Router::new().nest("/v1", v1::routes(shared_state))
+    Router::new()
+        .nest("/v1", v1::routes(shared_state))
+        // Otherwise the unknown API paths would reach the frontend fallback and get index.html
+        .fallback(|uri: Uri| async move {
+            crate::error::Error::NotFound(format!("No API route for {}", uri.path()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_unknown_api_route_not_found() {
+        use super::*;
+        use axum::{body::Body, http::Request, http::StatusCode};
+        use tower::ServiceExt;
+        let state = crate::store::test_support::details_state(polars::prelude::DataFrame::empty());
+        let app = Router::new()
+            .nest("/api", routes(State(state)))
+            .fallback(|| async { "index.html" });
+        let res = app
+            .clone()
+            .oneshot(Request::get("/api/v1/typo").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = app
+            .oneshot(Request::get("/replays/42").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
 }
@@ -0,0 +1,174 @@
+//! Debounced and cancellable requests for the windows that query the backend as the filters
+//! are edited.
+//!
+//! Each request gets a sequence number, a response is only kept when it is newer than the one
+//! shown, so a slow response never replaces the result of a later request. Sending a new
+//! request cancels the one in flight.
+
+use crate::api::client::ApiError;
+use poll_promise::Promise;
+use std::future::Future;
+
+/// How long the filters must stay unchanged before the request is sent, in seconds
+pub const DEBOUNCE_SECS: f64 = 0.3;
+
+/// A request that has been sent and has not completed yet
+struct InFlight<T: Send + 'static> {
+    /// The sequence number of the request
+    seq: u64,
+    /// The response of the request
+    promise: Promise<Result<T, ApiError>>,
+    /// Dropping the sender cancels the request
+    _cancel: tokio::sync::oneshot::Sender<()>,
+}
+
+/// The latest response of a window and the request that will replace it
+pub struct PendingRequest<T: Send + 'static> {
+    /// The sequence number of the next request
+    next_seq: u64,
+    /// The time, as `egui::InputState::time`, at which the debounced request is due
+    due_at: Option<f64>,
+    /// The request that has been sent
+    in_flight: Option<InFlight<T>>,
+    /// The latest response and the sequence number of its request
+    latest: Option<(u64, Result<T, ApiError>)>,
+}
+
+impl<T: Send + 'static> Default for PendingRequest<T> {
+    fn default() -> Self {
+        Self {
+            next_seq: 0,
+            due_at: None,
+            in_flight: None,
+            latest: None,
+        }
+    }
+}
+
+impl<T: Send + 'static> PendingRequest<T> {
+    /// Delays the request until the filters stop changing, any previous delay is restarted.
+    pub fn debounce(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|input| input.time);
+        self.due_at = Some(now + DEBOUNCE_SECS);
+        ctx.request_repaint_after(std::time::Duration::from_secs_f64(DEBOUNCE_SECS));
+    }
+
+    /// Whether the debounced request should be sent now, a repaint is scheduled otherwise.
+    pub fn is_due(&mut self, ctx: &egui::Context) -> bool {
+        let Some(due_at) = self.due_at else {
+            return false;
+        };
+        let now = ctx.input(|input| input.time);
+        if now >= due_at {
+            self.due_at = None;
+            true
+        } else {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(due_at - now));
+            false
+        }
+    }
+
+    /// Cancels the request in flight and sends a new one.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn send(&mut self, fut: impl Future<Output = Result<T, ApiError>> + Send + 'static) {
+        let (seq, cancel, fut) = self.cancellable(fut);
+        let promise = Promise::spawn_async(fut);
+        self.in_flight = Some(InFlight {
+            seq,
+            promise,
+            _cancel: cancel,
+        });
+    }
+
+    /// Cancels the request in flight and sends a new one.
+    #[cfg(target_arch = "wasm32")]
+    pub fn send(&mut self, fut: impl Future<Output = Result<T, ApiError>> + 'static) {
+        let (seq, cancel, fut) = self.cancellable(fut);
+        let promise = Promise::spawn_local(fut);
+        self.in_flight = Some(InFlight {
+            seq,
+            promise,
+            _cancel: cancel,
+        });
+    }
+
+    /// Numbers the request and wraps it so that it stops when the returned sender is dropped.
+    fn cancellable<F: Future<Output = Result<T, ApiError>>>(
+        &mut self,
+        fut: F,
+    ) -> (
+        u64,
+        tokio::sync::oneshot::Sender<()>,
+        impl Future<Output = Result<T, ApiError>>,
+    ) {
+        self.due_at = None;
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let (cancel, cancelled) = tokio::sync::oneshot::channel::<()>();
+        let fut = async move {
+            tokio::select! {
+                res = fut => res,
+                _ = cancelled => Err(ApiError::Cancelled),
+            }
+        };
+        (seq, cancel, fut)
+    }
+
+    /// Moves the completed request to the latest response, unless a newer one is shown.
+    pub fn poll(&mut self) {
+        let Some(InFlight {
+            seq,
+            promise,
+            _cancel,
+        }) = self.in_flight.take()
+        else {
+            return;
+        };
+        match promise.try_take() {
+            Ok(res) => {
+                if self.latest.as_ref().is_none_or(|(latest, _)| *latest < seq) {
+                    self.latest = Some((seq, res));
+                }
+            }
+            Err(promise) => {
+                self.in_flight = Some(InFlight {
+                    seq,
+                    promise,
+                    _cancel,
+                })
+            }
+        }
+    }
+
+    /// Whether a request is waiting for the debounce delay or for its response
+    pub fn is_pending(&self) -> bool {
+        self.due_at.is_some() || self.in_flight.is_some()
+    }
+
+    /// The latest response, it is kept while the next request is pending.
+    pub fn result(&self) -> Option<&Result<T, ApiError>> {
+        self.latest.as_ref().map(|(_, res)| res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_pending_request_keeps_latest() {
+        let mut pending: PendingRequest<u32> = PendingRequest::default();
+        let (slow_tx, slow_rx) = tokio::sync::oneshot::channel::<u32>();
+        pending.send(async move { Ok(slow_rx.await.unwrap_or_default()) });
+        assert!(pending.is_pending());
+        // The second request cancels the first one.
+        pending.send(async { Ok(2) });
+        while pending.is_pending() {
+            tokio::task::yield_now().await;
+            pending.poll();
+        }
+        assert_eq!(pending.result(), Some(&Ok(2)));
+        drop(slow_tx);
+    }
+}
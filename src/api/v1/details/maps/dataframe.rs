@@ -1,54 +1,27 @@
 //! Polars queries for the map frequency
 
 use super::{ListDetailsMapReq, ListDetailsMapRes, MapStats};
+use crate::api::v1::filter::dataframe::scan_details_with_slots;
 use crate::server::AppState;
 use polars::prelude::*;
 
 /// Gets the list of maps from the details.ipc file
 #[tracing::instrument(level = "debug", skip(state))]
-pub async fn get_map_freq(
+pub async fn query_map_freq(
     req: ListDetailsMapReq,
     state: AppState,
-) -> Result<ListDetailsMapRes, crate::error::Error> {
-    let meta = crate::meta::ResponseMetaBuilder::new();
-    let mut details_query = LazyFrame::scan_ipc(
-        format!("{}/{}", state.source_dir, crate::DETAILS_IPC),
-        Default::default(),
-    )?
-    .with_columns(vec![col("player_name")
+) -> Result<DataFrame, crate::error::Error> {
+    let details = scan_details_with_slots(&*state.store)?.with_columns(vec![col("player_name")
         .str()
         .split(lit("<sp/>"))
         .list()
         .last()
-        .alias("player_name")])
-    .filter(
-        col("ext_datetime")
-            .gt(lit(req.file_min_date))
-            .and(col("ext_datetime").lt(lit(req.file_max_date))),
-    );
-    if !req.title.is_empty() {
-        details_query = details_query.filter(
-            col("title")
-                .str()
-                .to_lowercase()
-                .str()
-                .contains_literal(lit(req.title.to_lowercase())),
-        );
-    }
-    if !req.file_name.is_empty() {
-        details_query = details_query.filter(
-            col("ext_fs_replay_file_name")
-                .str()
-                .to_lowercase()
-                .str()
-                .contains_literal(lit(req.file_name.to_lowercase())),
-        );
-    }
-    if !req.replay_id.is_empty() {
-        details_query = details_query.filter(col("ext_fs_id").eq(lit(req.replay_id)));
-    }
+        .alias("player_name")]);
+    let mut details_query = req.filter_replays(details);
+    // The top players of the replays, not only the players of the filter.
     let map_players_freq = details_query
         .clone()
+        .filter(req.ai_expr())
         .group_by([col("title")])
         .agg([col("player_name")
             .value_counts(true, true, "counts", true)
@@ -63,67 +36,56 @@ pub async fn get_map_freq(
                 ..Default::default()
             },
         );
-    if !req.player.is_empty() {
-        details_query = details_query.filter(
-            col("player_name")
-                .str()
-                .to_lowercase()
-                .str()
-                .contains_literal(lit(req.player.to_lowercase())),
-        );
-    }
+    details_query = details_query.filter(req.player_expr());
     let latest_replay_ids = details_query
         .clone()
         .group_by([col("title")])
         .agg([col("ext_fs_id").last().alias("latest_replay_id")]);
-    let res = tokio::task::spawn_blocking(|| {
-        details_query
-            .group_by([col("title")])
-            .agg([
-                col("title").count().alias("count"),
-                col("ext_datetime")
-                    .min()
-                    .dt()
-                    .to_string("%Y-%m-%dT%H:%M:%S")
-                    .alias("min_date"),
-                col("ext_datetime")
-                    .max()
-                    .dt()
-                    .to_string("%Y-%m-%dT%H:%M:%S")
-                    .alias("max_date"),
-            ])
-            .join(
-                latest_replay_ids,
-                &[col("title")],
-                &[col("title")],
-                JoinArgs::new(JoinType::Inner),
-            )
-            .join(
-                map_players_freq,
-                &[col("title")],
-                &[col("title")],
-                JoinArgs::new(JoinType::Inner),
-            )
-            .sort(
-                ["count"],
-                SortMultipleOptions {
-                    descending: vec![true],
-                    ..Default::default()
-                },
-            )
-            .limit(10000) // TODO: Unhardcode
-            .collect()
-    })
-    .await
-    .unwrap();
-    let res = res?;
+    let query = details_query
+        .group_by([col("title")])
+        .agg([
+            col("title").count().alias("count"),
+            col("ext_datetime")
+                .min()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("min_date"),
+            col("ext_datetime")
+                .max()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("max_date"),
+        ])
+        .join(
+            latest_replay_ids,
+            &[col("title")],
+            &[col("title")],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .join(
+            map_players_freq,
+            &[col("title")],
+            &[col("title")],
+            JoinArgs::new(JoinType::Inner),
+        );
+    let query = req
+        .sort_by_relevance(query, "title", &req.title)
+        .limit(state.queries.limits.max_rows);
+    state.queries.collect(query).await
+}
+
+/// Gets the maps as typed records
+pub async fn get_map_freq(
+    req: ListDetailsMapReq,
+    state: AppState,
+) -> Result<ListDetailsMapRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_map_freq(req, state).await?;
     tracing::trace!("ListDetailsMapRes: {:?}", res);
-    let data_str = crate::common::convert_df_to_json_data(&res)?;
-    tracing::trace!("Data: {}", data_str);
-    let data: Vec<MapStats> = serde_json::from_str(&data_str)?;
+    let data: Vec<MapStats> = crate::common::df_to_records(&res)?;
 
     Ok(ListDetailsMapRes {
-        meta: meta.build(),
+        meta: meta.with_total(data.len()).build(),
         data,
     })
 }
@@ -1,5 +1,8 @@
 //! Map count related queries
 
+use crate::api::client::ApiClient;
+use crate::api::pending::PendingRequest;
+use crate::api::validation::FieldErrors;
 use urlencoding::encode;
 
 pub mod ui;
@@ -13,70 +16,7 @@ pub mod server;
 use serde::{Deserialize, Serialize};
 
 /// Basic query request available for filtering replay maps
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ListDetailsMapReq {
-    /// The title of the map
-    #[serde(default)]
-    pub title: String,
-    /// A player that must have played in the game
-    #[serde(default)]
-    pub player: String,
-    /// Part of the file name
-    #[serde(default)]
-    pub file_name: String,
-    /// Part of the SHA256 hash
-    #[serde(default)]
-    pub replay_id: String,
-    /// Minimum bound of the file date
-    #[serde(default)]
-    pub file_min_date: chrono::NaiveDate,
-    /// Max bound of the file date
-    #[serde(default)]
-    pub file_max_date: chrono::NaiveDate,
-}
-
-impl Default for ListDetailsMapReq {
-    fn default() -> Self {
-        Self {
-            title: Default::default(),
-            player: Default::default(),
-            file_name: Default::default(),
-            replay_id: Default::default(),
-            file_min_date: Self::default_min_date(),
-            file_max_date: Self::default_max_date(),
-        }
-    }
-}
-
-impl ListDetailsMapReq {
-    /// Returns a new instance of the request with the unescaped values
-    pub fn from_escaped(self) -> Self {
-        Self {
-            title: urlencoding::decode(&self.title)
-                .unwrap_or_default()
-                .to_string(),
-            player: urlencoding::decode(&self.player)
-                .unwrap_or_default()
-                .to_string(),
-            file_name: urlencoding::decode(&self.file_name)
-                .unwrap_or_default()
-                .to_string(),
-            replay_id: self.replay_id,
-            file_min_date: self.file_min_date,
-            file_max_date: self.file_max_date,
-        }
-    }
-
-    /// Creates a default min date for dropdowns on date filters.
-    pub fn default_min_date() -> chrono::NaiveDate {
-        chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
-    }
-
-    /// Creates a default max date for dropdowns on date filters.
-    pub fn default_max_date() -> chrono::NaiveDate {
-        chrono::Local::now().naive_local().date()
-    }
-}
+pub type ListDetailsMapReq = crate::api::v1::filter::ReplayFilter;
 
 /// Basic query response available for filtering replay maps
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -144,7 +84,15 @@ pub struct SC2MapPicker {
 
     /// Contains the metadata related to the backend snapshot.
     #[serde(skip)]
-    map_list: Option<poll_promise::Promise<ListDetailsMapRes>>,
+    map_list: PendingRequest<ListDetailsMapRes>,
+
+    /// The client to the backend API
+    #[serde(skip)]
+    pub client: ApiClient,
+
+    /// The validation messages of the filters
+    #[serde(skip)]
+    field_errors: FieldErrors,
 
     /// The selected map
     #[serde(skip)]
@@ -152,43 +100,44 @@ pub struct SC2MapPicker {
 }
 
 impl SC2MapPicker {
-    async fn get_details_maps(filters: ListDetailsMapReq) -> ListDetailsMapRes {
-        let mut query_params: Vec<String> = vec![];
-        query_params.push(format!("title={}", encode(&filters.title)));
-        query_params.push(format!("player={}", encode(&filters.player)));
-        query_params.push(format!("file_name={}", encode(&filters.file_name)));
-        query_params.push(format!("replay_id={}", filters.replay_id));
-        query_params.push(format!(
-            "file_min_date={}",
-            encode(&filters.file_min_date.to_string())
-        ));
-        query_params.push(format!(
-            "file_max_date={}",
-            encode(&filters.file_max_date.to_string())
-        ));
-        let query_url = format!("/api/v1/details/maps?{}", query_params.join("&"));
-        ehttp::fetch_async(ehttp::Request::get(query_url))
-            .await
-            .map(|response| serde_json::from_slice(&response.bytes).unwrap_or_default())
-            .unwrap_or_default()
+    /// The validation messages of the filters, either local or returned by the server.
+    pub fn field_errors(&self) -> FieldErrors {
+        let server_errors = self
+            .map_list
+            .result()
+            .and_then(|res| res.as_ref().err())
+            .and_then(|e| e.field_errors());
+        match server_errors {
+            Some(errors) if self.field_errors.is_empty() => errors.clone(),
+            _ => self.field_errors.clone(),
+        }
+    }
+
+    /// Updates the filters and requests the maps again
+    pub fn apply_filter(&mut self, update: impl FnOnce(&mut ListDetailsMapReq)) {
+        update(&mut self.request);
+        self.req_details_maps();
     }
 
     /// Requests the async operation to get the details of the maps to the HTTP server.
     pub fn req_details_maps(&mut self) {
+        // The filters are validated locally first so that the messages show up
+        // next to the offending filter without a round trip.
+        let request = match self.request.clone().validate() {
+            Ok(request) => request,
+            Err(errors) => {
+                self.field_errors = errors;
+                return;
+            }
+        };
+        self.field_errors = FieldErrors::new();
+        let client = self.client.clone();
+        let fut = async move { client.details_maps(&request).await };
         #[cfg(target_arch = "wasm32")]
-        {
-            log::info!("Requesting details maps");
-            self.map_list = Some(poll_promise::Promise::spawn_local(Self::get_details_maps(
-                self.request.clone(),
-            )));
-        }
+        log::info!("Requesting details maps");
         #[cfg(not(target_arch = "wasm32"))]
-        {
-            tracing::info!("Requesting details maps");
-            self.map_list = Some(poll_promise::Promise::spawn_async(Self::get_details_maps(
-                self.request.clone(),
-            )));
-        }
+        tracing::info!("Requesting details maps");
+        self.map_list.send(fut);
     }
 }
 
@@ -220,4 +169,54 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_validate_list_details_map_req() {
+        let req = ListDetailsMapReq {
+            title: " Alcyone ".to_string(),
+            replay_id: "12".to_string(),
+            ..Default::default()
+        }
+        .validate()
+        .unwrap();
+        assert_eq!(req.title, "Alcyone");
+        let errors = ListDetailsMapReq {
+            replay_id: "deadbeef".to_string(),
+            file_min_date: ListDetailsMapReq::default_max_date(),
+            file_max_date: ListDetailsMapReq::default_min_date(),
+            ..Default::default()
+        }
+        .validate()
+        .unwrap_err();
+        assert!(errors.get("replay_id").is_some());
+        assert!(errors.get("file_min_date").is_some());
+        assert!(errors.get("title").is_none());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_get_map_freq_memory_store() {
+        use polars::prelude::*;
+        let state = crate::store::test_support::details_state(
+            df!(
+                "title" => ["Alcyone LE", "Alcyone LE", "Alcyone LE", "Alcyone LE", "Oceanborn LE", "Oceanborn LE"],
+                "player_name" => ["Serral", "Clem", "<sp/>Serral", "Maru", "Clem", "Maru"],
+                "player_control" => [2u8, 2, 2, 3, 2, 2],
+                "ext_fs_id" => [1u64, 1, 2, 2, 3, 3],
+                "ext_fs_replay_file_name" => ["a.SC2Replay", "a.SC2Replay", "b.SC2Replay", "b.SC2Replay", "c.SC2Replay", "c.SC2Replay"],
+                "ext_datetime" => [1_690_000_000_000i64, 1_690_000_000_000, 1_695_000_000_000, 1_695_000_000_000, 1_698_000_000_000, 1_698_000_000_000],
+            )
+            .unwrap(),
+        );
+        let req = ListDetailsMapReq {
+            player: "serral".to_string(),
+            ..Default::default()
+        };
+        let res = super::dataframe::get_map_freq(req, state).await.unwrap();
+        assert_eq!(res.data.len(), 1);
+        assert_eq!(res.data[0].title, "Alcyone LE");
+        assert_eq!(res.data[0].count, 2);
+        // Maru is an A.I. and the A.I. players are excluded by default.
+        assert!(!res.data[0].top_players.contains(&"Maru".to_string()));
+    }
 }
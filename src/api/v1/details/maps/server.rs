@@ -1,29 +1,25 @@
 //! Axum route handlers
 
-use super::{ListDetailsMapReq, ListDetailsMapRes};
+use super::ListDetailsMapReq;
+use crate::common::{DataFrameResponse, ResponseFormat};
 use crate::meta::ResponseMetaBuilder;
 use crate::server::AppState;
-use axum::{extract::Query, extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
 
 /// Filters the available maps based on the query parameters
 pub async fn route_query_maps(
-    req: Query<ListDetailsMapReq>,
+    headers: HeaderMap,
+    req: Result<Query<ListDetailsMapReq>, QueryRejection>,
     State(state): State<AppState>,
-) -> (StatusCode, Json<ListDetailsMapRes>) {
+) -> Result<Response, crate::error::Error> {
+    let Query(req) = req?;
     tracing::info!("Querying maps: {:?}", req);
-    let unescaped = req.0.from_escaped();
+    let req = req.validate()?;
     let meta = ResponseMetaBuilder::new();
-    match super::dataframe::get_map_freq(unescaped, state).await {
-        Ok(res) => (StatusCode::OK, Json(res)),
-        Err(e) => {
-            tracing::error!("Error: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ListDetailsMapRes {
-                    meta: meta.with_error(e.to_string()).build(),
-                    data: vec![],
-                }),
-            )
-        }
-    }
+    let res = super::dataframe::query_map_freq(req, state).await?;
+    Ok(DataFrameResponse::new(meta, res, ResponseFormat::from_headers(&headers)).into_response())
 }
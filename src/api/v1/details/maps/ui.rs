@@ -7,13 +7,13 @@ use super::{ListDetailsMapReq, SC2MapPicker};
 use eframe::egui;
 use egui::Ui;
 use egui::Widget;
-use egui_extras::{Column, DatePickerButton, TableBuilder};
+use egui_extras::{Column, TableBuilder};
 
 impl SC2MapPicker {
     /// Builds a portion of the UI to be used for the Maps table.
     fn table_div(&mut self, ui: &mut Ui, maps: &[MapStats]) {
         ui.vertical(|ui| {
-            ui.heading("Maps");
+            crate::app::show_table_heading(ui, "Maps", self.map_list.is_pending());
             ui.separator();
             ui.allocate_ui(
                 egui::Vec2::new(ui.available_width(), ui.available_height() * 0.5),
@@ -116,55 +116,21 @@ impl SC2MapPicker {
             .default_height(480.0)
             .open(is_open)
             .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    ui.label("Filters > ");
-                    ui.label("Map: ");
-                    if ui.text_edit_singleline(&mut self.request.title).changed() {
-                        self.req_details_maps();
-                    }
-                    ui.label("Player: ");
-                    if ui.text_edit_singleline(&mut self.request.player).changed() {
-                        self.req_details_maps();
-                    }
-                    ui.label("File Path: ");
-                    if ui
-                        .text_edit_singleline(&mut self.request.file_name)
-                        .changed()
-                    {
-                        self.req_details_maps();
-                    }
-                    ui.label("Snapshot Replay ID: ");
-                    if ui
-                        .text_edit_singleline(&mut self.request.replay_id)
-                        .changed()
-                    {
-                        self.req_details_maps();
-                    }
-                    ui.label("Min date: ");
-                    if DatePickerButton::new(&mut self.request.file_min_date)
-                        .id_salt("min_date")
-                        .ui(ui)
-                        .changed()
-                    {
-                        self.req_details_maps();
-                    }
-                    ui.label("Max date: ");
-                    if DatePickerButton::new(&mut self.request.file_max_date)
-                        .id_salt("max_date")
-                        .ui(ui)
-                        .changed()
-                    {
-                        self.req_details_maps();
-                    }
-                });
-                let map_list: Vec<MapStats> = if let Some(map_list) = &self.map_list {
-                    if let Some(map_list) = map_list.ready() {
-                        map_list.data.clone()
-                    } else {
+                let field_errors = self.field_errors();
+                if self.request.ui(ui, "maps", &field_errors) {
+                    self.map_list.debounce(ctx);
+                }
+                if self.map_list.is_due(ctx) {
+                    self.req_details_maps();
+                }
+                self.map_list.poll();
+                let map_list: Vec<MapStats> = match self.map_list.result() {
+                    Some(Ok(map_list)) => map_list.data.clone(),
+                    Some(Err(e)) => {
+                        crate::app::show_api_error(ui, e);
                         vec![]
                     }
-                } else {
-                    vec![]
+                    None => vec![],
                 };
                 self.request.file_min_date = map_list
                     .iter()
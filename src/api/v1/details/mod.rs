@@ -2,6 +2,11 @@
 
 pub mod maps;
 pub mod players;
+pub mod regions;
+pub mod replays;
+pub mod teams;
+pub mod versions;
+pub mod vs_ai;
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::server::AppState;
@@ -14,5 +19,10 @@ pub fn routes(state: State<AppState>) -> Router {
     Router::new()
         .route("/maps", get(maps::server::route_query_maps))
         .route("/players", get(players::server::route_query_players))
+        .route("/regions", get(regions::server::route_query_regions))
+        .route("/replays", get(replays::server::route_query_replays))
+        .route("/teams", get(teams::server::route_query_teams))
+        .route("/versions", get(versions::server::route_query_versions))
+        .route("/vs_ai", get(vs_ai::server::route_query_vs_ai))
         .with_state(state.0)
 }
@@ -1,217 +1,170 @@
-//! Polars queries for the map frequency
+//! Polars queries for the player frequency
 
+use super::identity::PlayerAliases;
 use super::{ListDetailsPlayerReq, ListDetailsPlayerRes, PlayerStats};
+use crate::api::v1::filter::dataframe::scan_details_with_slots;
 use crate::server::AppState;
 use polars::prelude::*;
 
+/// The value of an expression on the latest replay of the group
+fn latest(column: &str) -> Expr {
+    col(column)
+        .sort_by([col("ext_datetime")], SortMultipleOptions::default())
+        .last()
+}
+
+/// Splits the clan tag off the `player_name` into the `clan` column and adds the `toon_handle`
+/// and `identity` columns. The identity is the alias of the toon if any, otherwise the toon
+/// handle. The A.I. players have no toon and are identified by their name.
+pub fn with_identity(
+    details: LazyFrame,
+    aliases: &PlayerAliases,
+) -> Result<LazyFrame, crate::error::Error> {
+    let name_parts = col("player_name").str().split(lit("<sp/>"));
+    let details = details.with_columns([
+        when(name_parts.clone().list().len().gt(lit(1)))
+            .then(name_parts.clone().list().first())
+            .otherwise(lit(NULL).cast(DataType::String))
+            .alias("clan"),
+        name_parts.list().last().alias("player_name"),
+        concat_str(
+            [
+                col("player_toon_region").cast(DataType::String),
+                lit("-S2-"),
+                col("player_toon_realm").cast(DataType::String),
+                lit("-"),
+                col("player_toon_id").cast(DataType::String),
+            ],
+            "",
+            true,
+        )
+        .alias("toon_handle"),
+    ]);
+    let details = if aliases.0.is_empty() {
+        details.with_column(lit(NULL).cast(DataType::String).alias("alias"))
+    } else {
+        let (handles, names): (Vec<&str>, Vec<&str>) = aliases.handles().unzip();
+        let aliases = df!("toon_handle" => handles, "alias" => names)?;
+        details.join(
+            aliases.lazy(),
+            [col("toon_handle")],
+            [col("toon_handle")],
+            JoinArgs::new(JoinType::Left),
+        )
+    };
+    Ok(details.with_column(
+        coalesce(&[
+            col("alias"),
+            when(col("player_toon_id").eq(lit(0)))
+                .then(concat_str([lit("ai:"), col("player_name")], "", true))
+                .otherwise(col("toon_handle")),
+        ])
+        .alias("identity"),
+    ))
+}
+
 /// Gets the list of players from the details.ipc file
 #[tracing::instrument(level = "debug", skip(state))]
-pub async fn get_player_freq(
+pub async fn query_player_freq(
     req: ListDetailsPlayerReq,
     state: AppState,
-) -> Result<ListDetailsPlayerRes, crate::error::Error> {
-    let meta = crate::meta::ResponseMetaBuilder::new();
-    let mut query = LazyFrame::scan_ipc(
-        format!("{}/{}", state.source_dir, crate::DETAILS_IPC),
-        Default::default(),
-    )?
-    .with_columns(vec![
-        col("player_toon_region"),
-        col("player_toon_program_id"),
-        col("player_toon_realm"),
-        col("player_toon_id"),
-        col("player_name")
-            .str()
-            .split(lit("<sp/>"))
-            .list()
-            .last()
-            .alias("player_name"),
-    ])
-    .filter(
-        col("ext_datetime")
-            .gt(lit(req.file_min_date))
-            .and(col("ext_datetime").lt(lit(req.file_max_date))),
-    );
-    if !req.file_name.is_empty() {
-        query = query.filter(
-            col("ext_fs_replay_file_name")
-                .str()
-                .to_lowercase()
-                .str()
-                .contains_literal(lit(req.file_name.to_lowercase())),
-        );
-    }
-    if !req.replay_id.is_empty() {
-        query = query.filter(col("ext_fs_id").eq(lit(req.replay_id)));
-    }
-    let map_players_freq = query
+) -> Result<DataFrame, crate::error::Error> {
+    let details = with_identity(scan_details_with_slots(&*state.store)?, &state.aliases)?;
+    let mut query = req.filter_replays(details);
+    let map_players_freq = query.clone().group_by([col("identity")]).agg([col("title")
+        .value_counts(true, true, "counts", true)
+        .struct_()
+        .field_by_index(0)
+        .head(Some(5))
+        .alias("top_maps")]);
+    query = query.filter(req.player_expr());
+    let names = query
         .clone()
-        .group_by([
-            col("player_toon_region"),
-            col("player_toon_program_id"),
-            col("player_toon_realm"),
-            col("player_toon_id"),
-            col("player_name"),
+        .group_by([col("identity"), col("player_name")])
+        .agg([
+            latest("clan"),
+            col("ext_datetime")
+                .min()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("first_seen"),
+            col("ext_datetime")
+                .max()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("last_seen"),
         ])
-        .agg([col("title")
-            .value_counts(true, true, "counts", true)
-            .struct_()
-            .field_by_index(0)
-            .head(Some(5))
-            .alias("top_maps")])
-        .sort(
-            ["player_name"],
-            SortMultipleOptions {
-                descending: vec![true],
-                ..Default::default()
-            },
-        );
-    if !req.name.is_empty() {
-        query = query.filter(
-            col("player_name")
-                .str()
-                .to_lowercase()
-                .str()
-                .contains_literal(lit(req.name.to_lowercase())),
-        );
-    } else {
-        query = query.filter(col("player_name").str().contains_literal(lit("A.I")).not());
-    }
-    let latest_replay_ids = query
-        .clone()
-        .group_by([
-            col("player_toon_region"),
-            col("player_toon_program_id"),
-            col("player_toon_realm"),
-            col("player_toon_id"),
-            col("player_name"),
+        .group_by([col("identity")])
+        .agg([as_struct(vec![
+            col("player_name").alias("name"),
+            col("clan"),
+            col("first_seen"),
+            col("last_seen"),
         ])
-        .agg([col("ext_fs_id").last().alias("latest_replay_id")]);
-    let query_cp = query.clone();
+        .sort_by([col("last_seen")], SortMultipleOptions::default())
+        .alias("names")]);
 
-    let res = tokio::task::spawn_blocking(|| {
-        let per_race_stats = query_cp
-            .group_by([
-                col("player_toon_region"),
-                col("player_toon_program_id"),
-                col("player_toon_realm"),
-                col("player_toon_id"),
-                col("player_name"),
-                col("player_result"),
-                col("player_race"),
-            ])
-            .agg([col("player_result").count().alias("result_count")])
-            .sort(
-                [
-                    "result_count",
-                    "player_toon_region",
-                    "player_toon_program_id",
-                    "player_toon_realm",
-                    "player_toon_id",
-                    "player_name",
-                ],
-                SortMultipleOptions {
-                    descending: vec![true, true, true, true, true, true],
-                    ..Default::default()
-                },
-            );
-        query
-            .group_by([
-                col("player_toon_region"),
-                col("player_toon_program_id"),
-                col("player_toon_realm"),
-                col("player_toon_id"),
-                col("player_name"),
-            ])
-            .agg([
-                col("player_name").count().alias("count"),
-                col("ext_datetime")
-                    .min()
-                    .dt()
-                    .to_string("%Y-%m-%dT%H:%M:%S")
-                    .alias("min_date"),
-                col("ext_datetime")
-                    .max()
-                    .dt()
-                    .to_string("%Y-%m-%dT%H:%M:%S")
-                    .alias("max_date"),
-            ])
-            .join(
-                latest_replay_ids,
-                &[
-                    col("player_toon_region"),
-                    col("player_toon_program_id"),
-                    col("player_toon_realm"),
-                    col("player_toon_id"),
-                    col("player_name"),
-                ],
-                &[
-                    col("player_toon_region"),
-                    col("player_toon_program_id"),
-                    col("player_toon_realm"),
-                    col("player_toon_id"),
-                    col("player_name"),
-                ],
-                JoinArgs::new(JoinType::Inner),
-            )
-            .join(
-                map_players_freq,
-                &[
-                    col("player_toon_region"),
-                    col("player_toon_program_id"),
-                    col("player_toon_realm"),
-                    col("player_toon_id"),
-                    col("player_name"),
-                ],
-                &[
-                    col("player_toon_region"),
-                    col("player_toon_program_id"),
-                    col("player_toon_realm"),
-                    col("player_toon_id"),
-                    col("player_name"),
-                ],
-                JoinArgs::new(JoinType::Inner),
-            )
-            .join(
-                per_race_stats,
-                &[
-                    col("player_toon_region"),
-                    col("player_toon_program_id"),
-                    col("player_toon_realm"),
-                    col("player_toon_id"),
-                    col("player_name"),
-                ],
-                &[
-                    col("player_toon_region"),
-                    col("player_toon_program_id"),
-                    col("player_toon_realm"),
-                    col("player_toon_id"),
-                    col("player_name"),
-                ],
-                JoinArgs::new(JoinType::Left),
-            )
-            .sort(
-                ["count"],
-                SortMultipleOptions {
-                    descending: vec![true],
-                    ..Default::default()
-                },
-            )
-            .limit(10000) // TODO: Unhardcode
-            .collect()
-    })
-    .await
-    .unwrap();
-    let res = res?;
+    let query = query
+        .group_by([col("identity")])
+        .agg([
+            latest("player_name"),
+            latest("clan"),
+            col("alias").first(),
+            latest("player_toon_region"),
+            latest("player_toon_program_id"),
+            latest("player_toon_realm"),
+            latest("player_toon_id"),
+            col("toon_handle")
+                .unique()
+                .sort(Default::default())
+                .alias("toons"),
+            col("identity").count().alias("count"),
+            col("ext_datetime")
+                .min()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("min_date"),
+            col("ext_datetime")
+                .max()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("max_date"),
+            latest("ext_fs_id").alias("latest_replay_id"),
+            col("ai_difficulty").max(),
+        ])
+        // The aliases are shown instead of the latest name.
+        .with_column(coalesce(&[col("alias"), col("player_name")]).alias("player_name"))
+        .drop(["alias"])
+        .join(
+            map_players_freq,
+            [col("identity")],
+            [col("identity")],
+            JoinArgs::new(JoinType::Inner),
+        )
+        .join(
+            names,
+            [col("identity")],
+            [col("identity")],
+            JoinArgs::new(JoinType::Left),
+        );
+    let query = req
+        .sort_by_relevance(query, "player_name", &req.player)
+        .limit(state.queries.limits.max_rows);
+    state.queries.collect(query).await
+}
+
+/// Gets the players as typed records
+pub async fn get_player_freq(
+    req: ListDetailsPlayerReq,
+    state: AppState,
+) -> Result<ListDetailsPlayerRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_player_freq(req, state).await?;
     tracing::trace!("ListDetailsPlayerRes: {:?}", res);
-    let data_str = crate::common::convert_df_to_json_data(&res)?;
-    let mut data_str_cp = data_str.clone();
-    data_str_cp.truncate(300);
-    tracing::info!("Data: {}", data_str_cp);
-    let data: Vec<PlayerStats> = serde_json::from_str(&data_str)?;
+    let data: Vec<PlayerStats> = crate::common::df_to_records(&res)?;
 
     Ok(ListDetailsPlayerRes {
-        meta: meta.build(),
+        meta: meta.with_total(data.len()).build(),
         data,
     })
 }
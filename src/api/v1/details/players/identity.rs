@@ -0,0 +1,117 @@
+//! Player identity across name changes, clans and accounts
+//!
+//! A player is identified by the toon of the account, the names and clans are only labels
+//! that change over time. User defined aliases merge several toons, i.e. the accounts of a
+//! player on several regions, into one identity.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The toon handle of an account as shown by the game, i.e. `2-S2-1-315071`
+pub fn toon_handle(region: u64, realm: u64, id: u64) -> String {
+    format!("{}-S2-{}-{}", region, realm, id)
+}
+
+/// A name used by a player, with the dates it was seen in the snapshot
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PlayerName {
+    /// The name of the player
+    pub name: String,
+    /// The clan tag used with the name, if any
+    pub clan: Option<String>,
+    /// The first date the name was seen
+    pub first_seen: chrono::NaiveDateTime,
+    /// The last date the name was seen
+    pub last_seen: chrono::NaiveDateTime,
+}
+
+/// User defined aliases, by the name of the alias, the toon handles it merges.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerAliases(pub BTreeMap<String, Vec<String>>);
+
+impl PlayerAliases {
+    /// Loads the aliases from a JSON file, i.e. `{"Serral": ["2-S2-1-315071", "1-S2-1-1234"]}`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<Self, crate::error::Error> {
+        let contents = std::fs::read_to_string(path)?;
+        let aliases: Self = serde_json::from_str(&contents)?;
+        aliases.validate().map_err(crate::error::Error::Config)?;
+        Ok(aliases)
+    }
+
+    /// Checks that each toon handle is listed once, a handle under two aliases would be
+    /// counted for both.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen = BTreeSet::new();
+        let duplicates: BTreeSet<&str> = self
+            .0
+            .values()
+            .flatten()
+            .filter(|handle| !seen.insert(handle.as_str()))
+            .map(String::as_str)
+            .collect();
+        if duplicates.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Toon handles listed more than once: {}",
+                duplicates.into_iter().collect::<Vec<_>>().join(", ")
+            ))
+        }
+    }
+
+    /// The alias of a toon handle, if any
+    pub fn alias_of(&self, handle: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(_, handles)| handles.iter().any(|h| h == handle))
+            .map(|(alias, _)| alias.as_str())
+    }
+
+    /// The pairs of toon handle and alias, each handle once with its first alias like
+    /// `alias_of`, so that joining on them does not duplicate the rows.
+    pub fn handles(&self) -> impl Iterator<Item = (&str, &str)> {
+        let mut seen = BTreeSet::new();
+        self.0
+            .iter()
+            .flat_map(|(alias, handles)| {
+                handles
+                    .iter()
+                    .map(move |handle| (handle.as_str(), alias.as_str()))
+            })
+            .filter(move |(handle, _)| seen.insert(*handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_player_aliases() {
+        let aliases: PlayerAliases =
+            serde_json::from_str(r#"{"Serral": ["2-S2-1-315071", "1-S2-1-1234"]}"#).unwrap();
+        assert_eq!(aliases.alias_of(&toon_handle(1, 1, 1234)), Some("Serral"));
+        assert_eq!(aliases.alias_of("3-S2-1-1"), None);
+        assert_eq!(aliases.handles().count(), 2);
+        assert!(aliases.validate().is_ok());
+    }
+
+    #[test]
+    fn test_player_aliases_duplicate_handles() {
+        let aliases: PlayerAliases = serde_json::from_str(
+            r#"{"Serral": ["2-S2-1-315071", "2-S2-1-315071"], "ENCE": ["2-S2-1-315071"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            aliases.validate().unwrap_err(),
+            "Toon handles listed more than once: 2-S2-1-315071"
+        );
+        // The first alias wins, as in `alias_of`.
+        assert_eq!(
+            aliases.handles().collect::<Vec<_>>(),
+            [("2-S2-1-315071", "ENCE")]
+        );
+        assert_eq!(aliases.alias_of("2-S2-1-315071"), Some("ENCE"));
+    }
+}
@@ -1,7 +1,10 @@
 //! Player count related queries
 //!
-use urlencoding::encode;
+use crate::api::client::ApiClient;
+use crate::api::pending::PendingRequest;
+use crate::api::validation::FieldErrors;
 
+pub mod identity;
 pub mod ui;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -13,63 +16,7 @@ pub mod server;
 use serde::{Deserialize, Serialize};
 
 /// Basic query request available for filtering replay players
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ListDetailsPlayerReq {
-    /// A player that must have played in the game
-    #[serde(default)]
-    pub name: String,
-    /// Part of the file name
-    #[serde(default)]
-    pub file_name: String,
-    /// The replay id
-    #[serde(default)]
-    pub replay_id: String,
-    /// Minimum bound of the file date
-    #[serde(default)]
-    pub file_min_date: chrono::NaiveDate,
-    /// Max bound of the file date
-    #[serde(default)]
-    pub file_max_date: chrono::NaiveDate,
-}
-
-impl Default for ListDetailsPlayerReq {
-    fn default() -> Self {
-        Self {
-            name: Default::default(),
-            file_name: Default::default(),
-            replay_id: Default::default(),
-            file_min_date: Self::default_min_date(),
-            file_max_date: Self::default_max_date(),
-        }
-    }
-}
-
-impl ListDetailsPlayerReq {
-    /// Returns a new instance of the request with the unescaped values
-    pub fn from_escaped(self) -> Self {
-        Self {
-            name: urlencoding::decode(&self.name)
-                .unwrap_or_default()
-                .to_string(),
-            file_name: urlencoding::decode(&self.file_name)
-                .unwrap_or_default()
-                .to_string(),
-            replay_id: self.replay_id,
-            file_min_date: self.file_min_date,
-            file_max_date: self.file_max_date,
-        }
-    }
-
-    /// Creates a default min date for dropdowns on date filters.
-    pub fn default_min_date() -> chrono::NaiveDate {
-        chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
-    }
-
-    /// Creates a default max date for dropdowns on date filters.
-    pub fn default_max_date() -> chrono::NaiveDate {
-        chrono::Local::now().naive_local().date()
-    }
-}
+pub type ListDetailsPlayerReq = crate::api::v1::filter::ReplayFilter;
 
 /// Basic query response available for filtering replay players
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -83,9 +30,12 @@ pub struct ListDetailsPlayerRes {
 /// Basic response for playper frequency
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct PlayerStats {
-    /// The clan of the player
+    /// The identity of the player, the toon handle or the user defined alias
+    #[serde(default)]
+    pub identity: String,
+    /// The latest clan of the player
     pub clan: Option<String>,
-    /// The name of the player
+    /// The latest name of the player, or the alias
     #[serde(rename = "player_name")]
     pub name: String,
     /// The amount of replays for this player
@@ -109,6 +59,15 @@ pub struct PlayerStats {
     pub player_toon_realm: u64,
     /// Toon id
     pub player_toon_id: u64,
+    /// The difficulty of the A.I. players
+    #[serde(default)]
+    pub ai_difficulty: Option<u8>,
+    /// The names used by the player, the latest last
+    #[serde(default)]
+    pub names: Vec<identity::PlayerName>,
+    /// The toon handles of the accounts of the player, more than one for the aliases
+    #[serde(default)]
+    pub toons: Vec<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -128,12 +87,49 @@ pub struct PlayerRaceStats {
 }
 
 impl PlayerStats {
-    /// A visible label for the player blizzard link
+    /// The name of the player with the clan tag, or the difficulty for the A.I. players
+    pub fn label(&self) -> String {
+        match (&self.clan, self.ai_difficulty) {
+            (_, Some(difficulty)) => format!(
+                "{} ({})",
+                self.name,
+                crate::api::v1::filter::ai_difficulty_name(difficulty)
+            ),
+            (Some(clan), None) if !clan.is_empty() => format!("[{}] {}", clan, self.name),
+            _ => self.name.clone(),
+        }
+    }
+
+    /// The names used by the player with the dates they were seen, one per line
+    pub fn names_history(&self) -> String {
+        self.names
+            .iter()
+            .map(|name| {
+                format!(
+                    "{}: {} - {}",
+                    name.name,
+                    name.first_seen.format("%Y-%m-%d"),
+                    name.last_seen.format("%Y-%m-%d")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The decoded region of the toon
+    pub fn region(&self) -> Option<crate::api::v1::filter::Region> {
+        crate::api::v1::filter::Region::from_code(self.player_toon_region)
+    }
+
+    /// A visible label for the player blizzard link, the flag and name of the region
     pub fn blizzard_profile_link_title(&self) -> String {
-        format!(
-            "{}/{}/{}",
-            self.player_toon_region, self.player_toon_realm, self.player_toon_id
-        )
+        match self.region() {
+            Some(region) => format!("{} {}", region.label(), self.player_toon_id),
+            None => format!(
+                "{}/{}/{}",
+                self.player_toon_region, self.player_toon_realm, self.player_toon_id
+            ),
+        }
     }
     /// Creates a link to access the player info on the battle.net website
     pub fn blizzard_profile_link_href(&self) -> String {
@@ -153,7 +149,15 @@ pub struct SC2PlayerPicker {
 
     /// Contains the metadata related to the backend snapshot.
     #[serde(skip)]
-    player_list: Option<poll_promise::Promise<ListDetailsPlayerRes>>,
+    player_list: PendingRequest<ListDetailsPlayerRes>,
+
+    /// The client to the backend API
+    #[serde(skip)]
+    pub client: ApiClient,
+
+    /// The validation messages of the filters
+    #[serde(skip)]
+    field_errors: FieldErrors,
 
     /// The selected player
     #[serde(skip)]
@@ -161,41 +165,84 @@ pub struct SC2PlayerPicker {
 }
 
 impl SC2PlayerPicker {
-    async fn get_details_players(filters: ListDetailsPlayerReq) -> ListDetailsPlayerRes {
-        let mut query_params: Vec<String> = vec![];
-        query_params.push(format!("name={}", encode(&filters.name)));
-        query_params.push(format!("file_name={}", encode(&filters.file_name)));
-        query_params.push(format!("replay_id={}", filters.replay_id));
-        query_params.push(format!(
-            "file_min_date={}",
-            encode(&filters.file_min_date.to_string())
-        ));
-        query_params.push(format!(
-            "file_max_date={}",
-            encode(&filters.file_max_date.to_string())
-        ));
-        let query_url = format!("/api/v1/details/players?{}", query_params.join("&"));
-        ehttp::fetch_async(ehttp::Request::get(query_url))
-            .await
-            .map(|response| serde_json::from_slice(&response.bytes).unwrap_or_default())
-            .unwrap_or_default()
+    /// The validation messages of the filters, either local or returned by the server.
+    pub fn field_errors(&self) -> FieldErrors {
+        let server_errors = self
+            .player_list
+            .result()
+            .and_then(|res| res.as_ref().err())
+            .and_then(|e| e.field_errors());
+        match server_errors {
+            Some(errors) if self.field_errors.is_empty() => errors.clone(),
+            _ => self.field_errors.clone(),
+        }
+    }
+
+    /// Updates the filters and requests the players again
+    pub fn apply_filter(&mut self, update: impl FnOnce(&mut ListDetailsPlayerReq)) {
+        update(&mut self.request);
+        self.req_details_players();
     }
 
     /// Requests the async operation to get the details of the players to the HTTP server.
     pub fn req_details_players(&mut self) {
+        // The filters are validated locally first so that the messages show up
+        // next to the offending filter without a round trip.
+        let request = match self.request.clone().validate() {
+            Ok(request) => request,
+            Err(errors) => {
+                self.field_errors = errors;
+                return;
+            }
+        };
+        self.field_errors = FieldErrors::new();
+        let client = self.client.clone();
+        let fut = async move { client.details_players(&request).await };
         #[cfg(target_arch = "wasm32")]
-        {
-            log::info!("Requesting details players");
-            self.player_list = Some(poll_promise::Promise::spawn_local(
-                Self::get_details_players(self.request.clone()),
-            ));
-        }
+        log::info!("Requesting details players");
         #[cfg(not(target_arch = "wasm32"))]
-        {
-            tracing::info!("Requesting details players");
-            self.player_list = Some(poll_promise::Promise::spawn_async(
-                Self::get_details_players(self.request.clone()),
-            ));
-        }
+        tracing::info!("Requesting details players");
+        self.player_list.send(fut);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_get_player_freq_identity() {
+        use super::identity::PlayerAliases;
+        use super::*;
+        use polars::prelude::*;
+        let state = crate::store::test_support::details_state(
+            df!(
+                "title" => ["Alcyone LE", "Alcyone LE", "Oceanborn LE", "Oceanborn LE", "Oceanborn LE", "Oceanborn LE"],
+                "player_name" => ["Serral", "Maru", "ENCE<sp/>Serral", "Maru", "Serral2", "Clem"],
+                "player_control" => [2u8; 6],
+                "player_toon_region" => [2u64, 3, 2, 3, 1, 2],
+                "player_toon_program_id" => [21298u64; 6],
+                "player_toon_realm" => [1u64; 6],
+                "player_toon_id" => [315071u64, 1, 315071, 1, 1234, 2],
+                "ext_fs_id" => [1u64, 1, 2, 2, 3, 3],
+                "ext_datetime" => [1_690_000_000_000i64, 1_690_000_000_000, 1_695_000_000_000, 1_695_000_000_000, 1_698_000_000_000, 1_698_000_000_000],
+            )
+            .unwrap(),
+        );
+        let aliases: PlayerAliases =
+            serde_json::from_str(r#"{"Serral": ["2-S2-1-315071", "1-S2-1-1234"]}"#).unwrap();
+        let state = state.with_aliases(aliases);
+        let res = super::dataframe::get_player_freq(ListDetailsPlayerReq::default(), state)
+            .await
+            .unwrap();
+        assert_eq!(res.data.len(), 3);
+        let serral = res.data.iter().find(|p| p.identity == "Serral").unwrap();
+        assert_eq!(serral.count, 3);
+        assert_eq!(serral.name, "Serral");
+        assert_eq!(serral.toons, ["1-S2-1-1234", "2-S2-1-315071"]);
+        // The clan is kept and the renamed account is merged by the alias.
+        assert_eq!(serral.names.len(), 2);
+        assert_eq!(serral.names[0].name, "Serral");
+        assert_eq!(serral.names[0].clan.as_deref(), Some("ENCE"));
+        assert_eq!(serral.names[1].name, "Serral2");
     }
 }
@@ -7,13 +7,13 @@ use super::{ListDetailsPlayerReq, SC2PlayerPicker};
 use eframe::egui;
 use egui::Ui;
 use egui::Widget;
-use egui_extras::{Column, DatePickerButton, TableBuilder};
+use egui_extras::{Column, TableBuilder};
 
 impl SC2PlayerPicker {
     /// Builds a portion of the UI to be used for the Players table.
     fn table_div(&mut self, ui: &mut Ui, players: &[PlayerStats]) {
         ui.vertical(|ui| {
-            ui.heading("Players");
+            crate::app::show_table_heading(ui, "Players", self.player_list.is_pending());
             ui.separator();
             ui.allocate_ui(
                 egui::Vec2::new(ui.available_width(), ui.available_height() * 0.5),
@@ -36,8 +36,8 @@ impl SC2PlayerPicker {
             .column(Column::auto())
             .column(Column::remainder())
             .min_scrolled_height(0.0);
-        let selected_player_name: String = if let Some(player) = &self.selected_player {
-            player.name.clone()
+        let selected_player_identity: String = if let Some(player) = &self.selected_player {
+            player.identity.clone()
         } else {
             "".to_string()
         };
@@ -67,7 +67,7 @@ impl SC2PlayerPicker {
                     body.row(row_height, |mut row| {
                         let map_ratio = player.count as f32 / max_games_on_player as f32;
                         row.col(|ui| {
-                            if selected_player_name == player.name {
+                            if selected_player_identity == player.identity {
                                 ui.strong(idx.to_string());
                             } else if ui.button(idx.to_string()).clicked() {
                                 self.selected_player = Some(player.clone());
@@ -82,11 +82,15 @@ impl SC2PlayerPicker {
                             ui.add(bar);
                         });
                         row.col(|ui| {
-                            if selected_player_name == player.name {
-                                ui.strong(&player.name);
-                            } else if ui.button(&player.name).clicked() {
+                            if selected_player_identity == player.identity {
+                                ui.strong(player.label());
+                            } else if ui
+                                .button(player.label())
+                                .on_hover_text(player.names_history())
+                                .clicked()
+                            {
                                 self.selected_player = Some(player.clone());
-                                ui.label(&player.name);
+                                ui.label(player.label());
                             }
                         });
                         row.col(|ui| {
@@ -116,51 +120,21 @@ impl SC2PlayerPicker {
             .default_height(480.0)
             .open(is_open)
             .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    ui.label("Filters > ");
-                    ui.label("Player: ");
-                    if ui.text_edit_singleline(&mut self.request.name).changed() {
-                        self.req_details_players();
-                    }
-                    ui.label("File Path: ");
-                    if ui
-                        .text_edit_singleline(&mut self.request.file_name)
-                        .changed()
-                    {
-                        self.req_details_players();
-                    }
-                    ui.label("File Hash: ");
-                    if ui
-                        .text_edit_singleline(&mut self.request.replay_id)
-                        .changed()
-                    {
-                        self.req_details_players();
-                    }
-                    ui.label("Min date: ");
-                    if DatePickerButton::new(&mut self.request.file_min_date)
-                        .id_salt("min_date")
-                        .ui(ui)
-                        .changed()
-                    {
-                        self.req_details_players();
-                    }
-                    ui.label("Max date: ");
-                    if DatePickerButton::new(&mut self.request.file_max_date)
-                        .id_salt("max_date")
-                        .ui(ui)
-                        .changed()
-                    {
-                        self.req_details_players();
-                    }
-                });
-                let player_list: Vec<PlayerStats> = if let Some(player_list) = &self.player_list {
-                    if let Some(player_list) = player_list.ready() {
-                        player_list.data.clone()
-                    } else {
+                let field_errors = self.field_errors();
+                if self.request.ui(ui, "players", &field_errors) {
+                    self.player_list.debounce(ctx);
+                }
+                if self.player_list.is_due(ctx) {
+                    self.req_details_players();
+                }
+                self.player_list.poll();
+                let player_list: Vec<PlayerStats> = match self.player_list.result() {
+                    Some(Ok(player_list)) => player_list.data.clone(),
+                    Some(Err(e)) => {
+                        crate::app::show_api_error(ui, e);
                         vec![]
                     }
-                } else {
-                    vec![]
+                    None => vec![],
                 };
                 self.request.file_min_date = player_list
                     .iter()
@@ -0,0 +1,77 @@
+//! Polars queries for the region distribution
+
+use super::{ListDetailsRegionReq, ListDetailsRegionRes, RegionStats};
+use crate::api::v1::filter::dataframe::scan_details_with_slots;
+use crate::api::v1::filter::{columns, Region};
+use crate::server::AppState;
+use polars::prelude::*;
+
+/// The name of the region of each player, `Unknown` for the unknown codes
+pub fn region_name_expr() -> Expr {
+    let mut expr = lit("Unknown");
+    for region in Region::all() {
+        expr = when(col(columns::REGION).eq(lit(region.code())))
+            .then(lit(region.name()))
+            .otherwise(expr);
+    }
+    expr
+}
+
+/// Gets the games, players and maps per region and month from the details.ipc file
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn query_regions(
+    req: ListDetailsRegionReq,
+    state: AppState,
+) -> Result<DataFrame, crate::error::Error> {
+    let details = scan_details_with_slots(&*state.store)?;
+    let query = req
+        .filter_replays(details)
+        .filter(req.player_expr())
+        .with_columns([
+            col(columns::REGION)
+                .cast(DataType::UInt64)
+                .alias("region_code"),
+            region_name_expr().alias("region"),
+            col(columns::DATETIME)
+                .dt()
+                .to_string("%Y-%m")
+                .alias("period"),
+        ])
+        .group_by([col("region_code"), col("region"), col("period")])
+        .agg([
+            col(columns::REPLAY_ID)
+                .n_unique()
+                .cast(DataType::UInt32)
+                .alias("games"),
+            as_struct(vec![col("player_toon_realm"), col("player_toon_id")])
+                .n_unique()
+                .cast(DataType::UInt32)
+                .alias("players"),
+            col(columns::TITLE)
+                .n_unique()
+                .cast(DataType::UInt32)
+                .alias("maps"),
+        ])
+        .sort(
+            ["period", "region_code"],
+            SortMultipleOptions {
+                descending: vec![false, false],
+                ..Default::default()
+            },
+        );
+    state.queries.collect(query).await
+}
+
+/// Gets the region distribution as typed records
+pub async fn get_regions(
+    req: ListDetailsRegionReq,
+    state: AppState,
+) -> Result<ListDetailsRegionRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_regions(req, state).await?;
+    let data: Vec<RegionStats> = crate::common::df_to_records(&res)?;
+    Ok(ListDetailsRegionRes {
+        meta: meta.with_total(data.len()).build(),
+        data,
+    })
+}
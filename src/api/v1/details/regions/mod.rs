@@ -0,0 +1,38 @@
+//! The distribution of the games, players and maps per region over time
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+
+/// Basic query request available for filtering the region distribution
+pub type ListDetailsRegionReq = crate::api::v1::filter::ReplayFilter;
+
+/// Basic query response for the region distribution
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ListDetailsRegionRes {
+    /// Metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The data of the response
+    pub data: Vec<RegionStats>,
+}
+
+/// The activity of a region during a month
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct RegionStats {
+    /// The numeric region of the toons
+    pub region_code: u64,
+    /// The name of the region, i.e. `EU`
+    pub region: String,
+    /// The month, i.e. `2024-05`
+    pub period: String,
+    /// The number of replays
+    pub games: u32,
+    /// The number of distinct toons
+    pub players: u32,
+    /// The number of distinct maps
+    pub maps: u32,
+}
@@ -0,0 +1,25 @@
+//! Axum route handlers
+
+use super::ListDetailsRegionReq;
+use crate::common::{DataFrameResponse, ResponseFormat};
+use crate::meta::ResponseMetaBuilder;
+use crate::server::AppState;
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+
+/// Returns the games, players and maps per region and month for the query parameters
+pub async fn route_query_regions(
+    headers: HeaderMap,
+    req: Result<Query<ListDetailsRegionReq>, QueryRejection>,
+    State(state): State<AppState>,
+) -> Result<Response, crate::error::Error> {
+    let Query(req) = req?;
+    tracing::info!("Querying regions: {:?}", req);
+    let req = req.validate()?;
+    let meta = ResponseMetaBuilder::new();
+    let res = super::dataframe::query_regions(req, state).await?;
+    Ok(DataFrameResponse::new(meta, res, ResponseFormat::from_headers(&headers)).into_response())
+}
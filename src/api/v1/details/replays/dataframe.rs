@@ -0,0 +1,99 @@
+//! Polars queries for the replays
+
+use super::{ListDetailsReplayReq, ListDetailsReplayRes, ReplaySummary};
+use crate::api::v1::filter::columns;
+use crate::api::v1::filter::dataframe::{matchup_expr, scan_details_with_slots};
+use crate::server::AppState;
+use polars::prelude::*;
+
+/// Gets the replays from the details.ipc file, the latest first. The player filters select
+/// the replays with at least one matching player, all the players of the replay are listed.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn query_replays(
+    req: ListDetailsReplayReq,
+    state: AppState,
+) -> Result<DataFrame, crate::error::Error> {
+    let details =
+        scan_details_with_slots(&*state.store)?.with_columns(vec![col(columns::PLAYER_NAME)
+            .str()
+            .split(lit("<sp/>"))
+            .list()
+            .last()
+            .alias(columns::PLAYER_NAME)]);
+    let details = req.filter_replays(details);
+    let replay_ids = details
+        .clone()
+        .filter(req.player_expr())
+        .select([col(columns::REPLAY_ID)])
+        .unique(None, UniqueKeepStrategy::Any);
+    let query = details
+        .join(
+            replay_ids,
+            [col(columns::REPLAY_ID)],
+            [col(columns::REPLAY_ID)],
+            JoinArgs::new(JoinType::Semi),
+        )
+        .with_column(matchup_expr().alias("matchup"))
+        .group_by([col(columns::REPLAY_ID)])
+        .agg([
+            col(columns::DATETIME).first(),
+            col(columns::TITLE).first(),
+            col(columns::FILE_NAME).first().alias("file_name"),
+            col(columns::GAME_VERSION)
+                .first()
+                .cast(DataType::String)
+                .alias("game_version"),
+            col(columns::DURATION)
+                .first()
+                .cast(DataType::UInt32)
+                .alias("duration"),
+            col("matchup").first(),
+            concat_str(
+                [
+                    col(columns::PLAYER_NAME),
+                    lit(" ("),
+                    col(columns::RACE),
+                    lit(")"),
+                ],
+                "",
+                true,
+            )
+            .alias("players"),
+        ])
+        .sort(
+            [columns::DATETIME],
+            SortMultipleOptions {
+                descending: vec![true],
+                ..Default::default()
+            },
+        )
+        .select([
+            col(columns::REPLAY_ID).alias("replay_id"),
+            col(columns::DATETIME)
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("datetime"),
+            col(columns::TITLE),
+            col("file_name"),
+            col("game_version"),
+            col("duration"),
+            col("matchup"),
+            col("players"),
+        ])
+        .limit(state.queries.limits.max_rows);
+    state.queries.collect(query).await
+}
+
+/// Gets the replays as typed records
+pub async fn get_replays(
+    req: ListDetailsReplayReq,
+    state: AppState,
+) -> Result<ListDetailsReplayRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_replays(req, state).await?;
+    let data: Vec<ReplaySummary> = crate::common::df_to_records(&res)?;
+    Ok(ListDetailsReplayRes {
+        meta: meta.with_total(data.len()).build(),
+        data,
+    })
+}
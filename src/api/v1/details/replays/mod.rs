@@ -0,0 +1,42 @@
+//! The replays matching the filters, one row per replay
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+
+/// Basic query request available for filtering the replays
+pub type ListDetailsReplayReq = crate::api::v1::filter::ReplayFilter;
+
+/// Basic query response for the replays
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ListDetailsReplayRes {
+    /// Metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The data of the response
+    pub data: Vec<ReplaySummary>,
+}
+
+/// A replay and its players
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ReplaySummary {
+    /// The snapshot replay id
+    pub replay_id: u64,
+    /// The date of the game
+    pub datetime: chrono::NaiveDateTime,
+    /// The map of the game
+    pub title: String,
+    /// The path of the replay file
+    pub file_name: String,
+    /// The version of the game, i.e. `5.0.12.92440`
+    pub game_version: Option<String>,
+    /// The duration of the game in seconds
+    pub duration: Option<u32>,
+    /// The matchup of the game, i.e. `TvZ`
+    pub matchup: String,
+    /// The players and their race, i.e. `Serral (Zerg)`
+    pub players: Vec<String>,
+}
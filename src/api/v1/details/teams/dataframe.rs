@@ -0,0 +1,93 @@
+//! Polars queries for the team frequency
+
+use super::{ListDetailsTeamReq, ListDetailsTeamRes, TeamStats};
+use crate::api::v1::filter::dataframe::{result_expr, scan_details_with_slots, with_team_layout};
+use crate::api::v1::filter::{columns, GameResult, TeamLayout};
+use crate::server::AppState;
+use polars::prelude::*;
+
+/// Gets the partnerships of the team games from the details.ipc file
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn query_team_freq(
+    req: ListDetailsTeamReq,
+    state: AppState,
+) -> Result<DataFrame, crate::error::Error> {
+    let details =
+        scan_details_with_slots(&*state.store)?.with_columns(vec![col(columns::PLAYER_NAME)
+            .str()
+            .split(lit("<sp/>"))
+            .list()
+            .last()
+            .alias(columns::PLAYER_NAME)]);
+    let mut query = with_team_layout(details.filter(req.slot_expr())).filter(req.replay_expr());
+    if req.layout.is_none() {
+        let team_layouts: Vec<Expr> = TeamLayout::all()
+            .iter()
+            .filter(|layout| layout.is_team_game())
+            .map(|layout| col(columns::TEAM_LAYOUT).eq(lit(layout.name())))
+            .collect();
+        query = query.filter(team_layouts.into_iter().reduce(|a, b| a.or(b)).unwrap());
+    }
+    // The player filters select the teams with at least one matching player.
+    let teams = query
+        .with_column(req.player_expr().alias("__matches"))
+        .group_by([col(columns::REPLAY_ID), col(columns::TEAM_ID)])
+        .agg([
+            col(columns::PLAYER_NAME)
+                .sort(Default::default())
+                .alias("players"),
+            col(columns::TEAM_LAYOUT).first(),
+            result_expr(GameResult::Win).first().alias("__win"),
+            result_expr(GameResult::Loss).first().alias("__loss"),
+            col(columns::DATETIME).first(),
+            col("__matches").any(true),
+        ])
+        .filter(col("__matches"))
+        .with_column(col("players").list().join(lit(" & "), true).alias("__team"));
+    let query = teams
+        .group_by([col("__team")])
+        .agg([
+            col("players").first(),
+            col(columns::TEAM_LAYOUT).first(),
+            col("__team").count().alias("count"),
+            col("__win").sum().cast(DataType::UInt32).alias("wins"),
+            col("__loss").sum().cast(DataType::UInt32).alias("losses"),
+            col(columns::DATETIME)
+                .min()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("min_date"),
+            col(columns::DATETIME)
+                .max()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("max_date"),
+            col(columns::REPLAY_ID).max().alias("latest_replay_id"),
+        ])
+        .drop(["__team"])
+        .sort(
+            ["count"],
+            SortMultipleOptions {
+                descending: vec![true],
+                ..Default::default()
+            },
+        )
+        .limit(state.queries.limits.max_rows);
+    state.queries.collect(query).await
+}
+
+/// Gets the teams as typed records
+pub async fn get_team_freq(
+    req: ListDetailsTeamReq,
+    state: AppState,
+) -> Result<ListDetailsTeamRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_team_freq(req, state).await?;
+    tracing::trace!("ListDetailsTeamRes: {:?}", res);
+    let data: Vec<TeamStats> = crate::common::df_to_records(&res)?;
+
+    Ok(ListDetailsTeamRes {
+        meta: meta.with_total(data.len()).build(),
+        data,
+    })
+}
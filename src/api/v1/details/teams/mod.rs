@@ -0,0 +1,160 @@
+//! Team related queries, the results of team games are attributed to the partnerships
+
+use crate::api::client::ApiClient;
+use crate::api::pending::PendingRequest;
+use crate::api::validation::FieldErrors;
+
+pub mod ui;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+
+/// Basic query request available for filtering the teams, when no layout is requested only
+/// the team games (2v2, 3v3 and 4v4) are included.
+pub type ListDetailsTeamReq = crate::api::v1::filter::ReplayFilter;
+
+/// Basic query response available for filtering the teams
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ListDetailsTeamRes {
+    /// Metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The data of the response
+    pub data: Vec<TeamStats>,
+}
+
+/// Basic response for the team frequency
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TeamStats {
+    /// The names of the players of the team, sorted
+    pub players: Vec<String>,
+    /// The layout of the games of the team, i.e. `2v2`
+    pub team_layout: String,
+    /// The amount of replays for this team
+    pub count: u32,
+    /// The number of wins of the team
+    pub wins: u32,
+    /// The number of defeats of the team
+    pub losses: u32,
+    /// The minimum date of the snapshot taken
+    pub min_date: chrono::NaiveDateTime,
+    /// The maximum date of the snapshot taken
+    pub max_date: chrono::NaiveDateTime,
+    /// The latest replay of the team
+    pub latest_replay_id: u64,
+}
+
+impl TeamStats {
+    /// The players of the team, as a label
+    pub fn name(&self) -> String {
+        self.players.join(" & ")
+    }
+
+    /// The ratio of wins over the decided games
+    pub fn win_ratio(&self) -> f32 {
+        let decided = self.wins + self.losses;
+        if decided == 0 {
+            0.0
+        } else {
+            self.wins as f32 / decided as f32
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct SC2TeamPicker {
+    /// A set of filters for the teams
+    #[serde(skip)]
+    request: ListDetailsTeamReq,
+
+    /// The teams matching the filters
+    #[serde(skip)]
+    team_list: PendingRequest<ListDetailsTeamRes>,
+
+    /// The client to the backend API
+    #[serde(skip)]
+    pub client: ApiClient,
+
+    /// The validation messages of the filters
+    #[serde(skip)]
+    field_errors: FieldErrors,
+
+    /// The selected team
+    #[serde(skip)]
+    pub selected_team: Option<TeamStats>,
+}
+
+impl SC2TeamPicker {
+    /// The validation messages of the filters, either local or returned by the server.
+    pub fn field_errors(&self) -> FieldErrors {
+        let server_errors = self
+            .team_list
+            .result()
+            .and_then(|res| res.as_ref().err())
+            .and_then(|e| e.field_errors());
+        match server_errors {
+            Some(errors) if self.field_errors.is_empty() => errors.clone(),
+            _ => self.field_errors.clone(),
+        }
+    }
+
+    /// Requests the async operation to get the teams to the HTTP server.
+    pub fn req_details_teams(&mut self) {
+        let request = match self.request.clone().validate() {
+            Ok(request) => request,
+            Err(errors) => {
+                self.field_errors = errors;
+                return;
+            }
+        };
+        self.field_errors = FieldErrors::new();
+        let client = self.client.clone();
+        let fut = async move { client.details_teams(&request).await };
+        #[cfg(target_arch = "wasm32")]
+        log::info!("Requesting details teams");
+        #[cfg(not(target_arch = "wasm32"))]
+        tracing::info!("Requesting details teams");
+        self.team_list.send(fut);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_get_team_freq_memory_store() {
+        use super::*;
+        use polars::prelude::*;
+        let state = crate::store::test_support::details_state(
+            df!(
+                "title" => ["Alcyone LE"; 12],
+                "player_name" => ["Serral", "Reynor", "Clem", "Maru", "Serral", "Reynor", "Clem", "Maru", "Serral", "A.I. 1 (Elite)", "Clem", "Maru"],
+                "player_team_id" => [0u8, 0, 1, 1, 0, 0, 1, 1, 0, 1, 0, 1],
+                "player_control" => [2u8, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 2],
+                "player_result" => [1u8, 1, 2, 2, 2, 2, 1, 1, 1, 2, 1, 2],
+                "ext_fs_id" => [1u64, 1, 1, 1, 2, 2, 2, 2, 3, 3, 4, 4],
+                "ext_datetime" => [1_690_000_000_000i64; 12],
+            )
+            .unwrap(),
+        );
+        let res = super::dataframe::get_team_freq(ListDetailsTeamReq::default(), state)
+            .await
+            .unwrap();
+        // The vs A.I. and 1v1 games are not team games.
+        assert_eq!(res.data.len(), 2);
+        let serral_reynor = res
+            .data
+            .iter()
+            .find(|team| team.players == ["Reynor", "Serral"])
+            .unwrap();
+        assert_eq!(serral_reynor.team_layout, "2v2");
+        assert_eq!(serral_reynor.count, 2);
+        assert_eq!(serral_reynor.wins, 1);
+        assert_eq!(serral_reynor.losses, 1);
+    }
+}
@@ -0,0 +1,25 @@
+//! Axum route handlers
+
+use super::ListDetailsTeamReq;
+use crate::common::{DataFrameResponse, ResponseFormat};
+use crate::meta::ResponseMetaBuilder;
+use crate::server::AppState;
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+
+/// Filters the available teams based on the query parameters
+pub async fn route_query_teams(
+    headers: HeaderMap,
+    req: Result<Query<ListDetailsTeamReq>, QueryRejection>,
+    State(state): State<AppState>,
+) -> Result<Response, crate::error::Error> {
+    let Query(req) = req?;
+    tracing::info!("Querying teams: {:?}", req);
+    let req = req.validate()?;
+    let meta = ResponseMetaBuilder::new();
+    let res = super::dataframe::query_team_freq(req, state).await?;
+    Ok(DataFrameResponse::new(meta, res, ResponseFormat::from_headers(&headers)).into_response())
+}
@@ -0,0 +1,118 @@
+//! Contains the UI for the team frequency table.
+
+use crate::app::AppEvent;
+
+use super::{SC2TeamPicker, TeamStats};
+use eframe::egui;
+use egui::Ui;
+use egui_extras::{Column, TableBuilder};
+
+impl SC2TeamPicker {
+    /// Builds a table for egui with the teams and their results.
+    fn table_inner(&mut self, ui: &mut Ui, teams: &[TeamStats]) {
+        let table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::initial(100.0).at_least(40.0).clip(true))
+            .column(Column::initial(160.0).at_least(40.0).clip(true))
+            .column(Column::auto())
+            .column(Column::remainder())
+            .min_scrolled_height(0.0);
+        let selected_team_name: String = self
+            .selected_team
+            .as_ref()
+            .map(|team| team.name())
+            .unwrap_or_default();
+
+        table
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Row");
+                });
+                header.col(|ui| {
+                    ui.strong("Frequency");
+                });
+                header.col(|ui| {
+                    ui.strong("Team");
+                });
+                header.col(|ui| {
+                    ui.strong("Layout");
+                });
+                header.col(|ui| {
+                    ui.strong("Wins / Losses");
+                });
+            })
+            .body(|mut body| {
+                let max_games_of_team = teams.iter().map(|x| x.count).max().unwrap_or(0);
+                for (idx, team) in teams.iter().enumerate() {
+                    let row_height = 18.0;
+                    body.row(row_height, |mut row| {
+                        let team_ratio = team.count as f32 / max_games_of_team as f32;
+                        let team_name = team.name();
+                        row.col(|ui| {
+                            ui.label(idx.to_string());
+                        });
+                        row.col(|ui| {
+                            let bar = egui::ProgressBar::new(team_ratio)
+                                .desired_width(ui.available_width())
+                                .text(team.count.to_string());
+                            ui.add(bar);
+                        });
+                        row.col(|ui| {
+                            if selected_team_name == team_name {
+                                ui.strong(&team_name);
+                            } else if ui.button(&team_name).clicked() {
+                                self.selected_team = Some(team.clone());
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.label(&team.team_layout);
+                        });
+                        row.col(|ui| {
+                            ui.label(format!(
+                                "{} / {} ({:.0}%)",
+                                team.wins,
+                                team.losses,
+                                team.win_ratio() * 100.0
+                            ));
+                        });
+                    });
+                }
+            });
+    }
+
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        is_open: &mut bool,
+        _tx: tokio::sync::mpsc::Sender<AppEvent>,
+    ) {
+        egui::Window::new("Team Selection")
+            .default_width(480.0)
+            .default_height(480.0)
+            .open(is_open)
+            .show(ctx, |ui| {
+                let field_errors = self.field_errors();
+                if self.request.ui(ui, "teams", &field_errors) {
+                    self.team_list.debounce(ctx);
+                }
+                if self.team_list.is_due(ctx) {
+                    self.req_details_teams();
+                }
+                self.team_list.poll();
+                let team_list: Vec<TeamStats> = match self.team_list.result() {
+                    Some(Ok(team_list)) => team_list.data.clone(),
+                    Some(Err(e)) => {
+                        crate::app::show_api_error(ui, e);
+                        vec![]
+                    }
+                    None => vec![],
+                };
+                crate::app::show_table_heading(ui, "Teams", self.team_list.is_pending());
+                ui.separator();
+                self.table_inner(ui, &team_list);
+            });
+    }
+}
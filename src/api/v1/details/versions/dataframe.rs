@@ -0,0 +1,63 @@
+//! Polars queries for the game versions
+
+use super::{ListDetailsVersionReq, ListDetailsVersionRes, VersionStats};
+use crate::api::v1::filter::columns;
+use crate::api::v1::filter::dataframe::{scan_details_with_slots, version_key_expr};
+use crate::server::AppState;
+use polars::prelude::*;
+
+/// Gets the game versions of the replays from the details.ipc file, the latest first
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn query_versions(
+    req: ListDetailsVersionReq,
+    state: AppState,
+) -> Result<DataFrame, crate::error::Error> {
+    let details = scan_details_with_slots(&*state.store)?;
+    let query = req
+        .filter_replays(details)
+        .filter(req.player_expr())
+        .group_by([col(columns::GAME_VERSION)
+            .cast(DataType::String)
+            .alias("game_version")])
+        .agg([
+            col(columns::BASE_BUILD)
+                .first()
+                .cast(DataType::UInt32)
+                .alias("base_build"),
+            version_key_expr().first().alias("version_key"),
+            col(columns::REPLAY_ID).n_unique().alias("count"),
+            col(columns::DATETIME)
+                .min()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("min_date"),
+            col(columns::DATETIME)
+                .max()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("max_date"),
+        ])
+        .sort_by_exprs(
+            [col("version_key"), col("base_build")],
+            SortMultipleOptions {
+                descending: vec![true, true],
+                ..Default::default()
+            },
+        )
+        .drop(["version_key"]);
+    state.queries.collect(query).await
+}
+
+/// Gets the game versions as typed records
+pub async fn get_versions(
+    req: ListDetailsVersionReq,
+    state: AppState,
+) -> Result<ListDetailsVersionRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_versions(req, state).await?;
+    let data: Vec<VersionStats> = crate::common::df_to_records(&res)?;
+    Ok(ListDetailsVersionRes {
+        meta: meta.with_total(data.len()).build(),
+        data,
+    })
+}
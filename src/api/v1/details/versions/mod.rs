@@ -0,0 +1,36 @@
+//! The game versions of the replays, to define the patch eras
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+
+/// Basic query request available for filtering the versions
+pub type ListDetailsVersionReq = crate::api::v1::filter::ReplayFilter;
+
+/// Basic query response for the game versions
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ListDetailsVersionRes {
+    /// Metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The data of the response
+    pub data: Vec<VersionStats>,
+}
+
+/// The replays recorded with a version of the game
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct VersionStats {
+    /// The version of the game, i.e. `5.0.12.92440`
+    pub game_version: String,
+    /// The base build of the game
+    pub base_build: Option<u32>,
+    /// The amount of replays for this version
+    pub count: u32,
+    /// The minimum date of the snapshot taken
+    pub min_date: chrono::NaiveDateTime,
+    /// The maximum date of the snapshot taken
+    pub max_date: chrono::NaiveDateTime,
+}
@@ -0,0 +1,25 @@
+//! Axum route handlers
+
+use super::ListDetailsVersionReq;
+use crate::common::{DataFrameResponse, ResponseFormat};
+use crate::meta::ResponseMetaBuilder;
+use crate::server::AppState;
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+
+/// Lists the game versions of the replays matching the query parameters
+pub async fn route_query_versions(
+    headers: HeaderMap,
+    req: Result<Query<ListDetailsVersionReq>, QueryRejection>,
+    State(state): State<AppState>,
+) -> Result<Response, crate::error::Error> {
+    let Query(req) = req?;
+    tracing::info!("Querying versions: {:?}", req);
+    let req = req.validate()?;
+    let meta = ResponseMetaBuilder::new();
+    let res = super::dataframe::query_versions(req, state).await?;
+    Ok(DataFrameResponse::new(meta, res, ResponseFormat::from_headers(&headers)).into_response())
+}
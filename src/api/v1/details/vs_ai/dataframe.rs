@@ -0,0 +1,86 @@
+//! Polars queries for the games against the A.I.
+
+use super::{ListDetailsVsAiReq, ListDetailsVsAiRes, VsAiStats};
+use crate::api::v1::filter::dataframe::{result_expr, scan_details_with_slots};
+use crate::api::v1::filter::{columns, AiPlayers, GameResult, TeamLayout};
+use crate::server::AppState;
+use polars::prelude::*;
+
+/// Gets the results of the human players against the A.I. from the details.ipc file
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn query_vs_ai(
+    req: ListDetailsVsAiReq,
+    state: AppState,
+) -> Result<DataFrame, crate::error::Error> {
+    let req = ListDetailsVsAiReq {
+        layout: Some(TeamLayout::VsAi),
+        ai: AiPlayers::Exclude,
+        ..req
+    };
+    let details =
+        scan_details_with_slots(&*state.store)?.with_columns(vec![col(columns::PLAYER_NAME)
+            .str()
+            .split(lit("<sp/>"))
+            .list()
+            .last()
+            .alias(columns::PLAYER_NAME)]);
+    // The difficulty of the opponents is taken before the A.I. rows are removed.
+    let query = req
+        .filter_replays(details)
+        .with_column(
+            col(columns::AI_DIFFICULTY)
+                .max()
+                .over([col(columns::REPLAY_ID)])
+                .alias(columns::AI_DIFFICULTY),
+        )
+        .filter(req.player_expr());
+    let query = query
+        .group_by([col(columns::PLAYER_NAME), col(columns::AI_DIFFICULTY)])
+        .agg([
+            col(columns::PLAYER_NAME).count().alias("count"),
+            result_expr(GameResult::Win)
+                .sum()
+                .cast(DataType::UInt32)
+                .alias("wins"),
+            result_expr(GameResult::Loss)
+                .sum()
+                .cast(DataType::UInt32)
+                .alias("losses"),
+            col(columns::DATETIME)
+                .min()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("min_date"),
+            col(columns::DATETIME)
+                .max()
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("max_date"),
+            col(columns::REPLAY_ID).max().alias("latest_replay_id"),
+        ])
+        .sort(
+            ["count"],
+            SortMultipleOptions {
+                descending: vec![true],
+                ..Default::default()
+            },
+        )
+        .limit(state.queries.limits.max_rows);
+    state.queries.collect(query).await
+}
+
+/// Gets the games against the A.I. as typed records
+pub async fn get_vs_ai(
+    req: ListDetailsVsAiReq,
+    state: AppState,
+) -> Result<ListDetailsVsAiRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_vs_ai(req, state).await?;
+    tracing::trace!("ListDetailsVsAiRes: {:?}", res);
+    let data: Vec<VsAiStats> = crate::common::df_to_records(&res)?;
+
+    Ok(ListDetailsVsAiRes {
+        meta: meta.with_total(data.len()).build(),
+        data,
+    })
+}
@@ -0,0 +1,111 @@
+//! Statistics of the games against the A.I., i.e. practice sessions
+
+use crate::api::client::ApiClient;
+use crate::api::pending::PendingRequest;
+use crate::api::validation::FieldErrors;
+
+pub mod ui;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+use serde::{Deserialize, Serialize};
+
+/// Basic query request available for filtering the games against the A.I., the layout and
+/// the A.I. options of the filter are ignored.
+pub type ListDetailsVsAiReq = crate::api::v1::filter::ReplayFilter;
+
+/// Basic query response for the games against the A.I.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ListDetailsVsAiRes {
+    /// Metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The data of the response
+    pub data: Vec<VsAiStats>,
+}
+
+/// The results of a player against an A.I. difficulty
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct VsAiStats {
+    /// The name of the human player
+    pub player_name: String,
+    /// The highest difficulty of the A.I. opponents, if known
+    pub ai_difficulty: Option<u8>,
+    /// The amount of replays
+    pub count: u32,
+    /// The number of wins of the player
+    pub wins: u32,
+    /// The number of defeats of the player
+    pub losses: u32,
+    /// The minimum date of the snapshot taken
+    pub min_date: chrono::NaiveDateTime,
+    /// The maximum date of the snapshot taken
+    pub max_date: chrono::NaiveDateTime,
+    /// The latest replay of the player against this difficulty
+    pub latest_replay_id: u64,
+}
+
+impl VsAiStats {
+    /// The name of the difficulty of the A.I. opponents
+    pub fn difficulty_name(&self) -> &'static str {
+        self.ai_difficulty
+            .map_or("Unknown", crate::api::v1::filter::ai_difficulty_name)
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct SC2VsAiStats {
+    /// A set of filters for the games
+    #[serde(skip)]
+    request: ListDetailsVsAiReq,
+
+    /// The statistics matching the filters
+    #[serde(skip)]
+    stats: PendingRequest<ListDetailsVsAiRes>,
+
+    /// The client to the backend API
+    #[serde(skip)]
+    pub client: ApiClient,
+
+    /// The validation messages of the filters
+    #[serde(skip)]
+    field_errors: FieldErrors,
+}
+
+impl SC2VsAiStats {
+    /// The validation messages of the filters, either local or returned by the server.
+    pub fn field_errors(&self) -> FieldErrors {
+        let server_errors = self
+            .stats
+            .result()
+            .and_then(|res| res.as_ref().err())
+            .and_then(|e| e.field_errors());
+        match server_errors {
+            Some(errors) if self.field_errors.is_empty() => errors.clone(),
+            _ => self.field_errors.clone(),
+        }
+    }
+
+    /// Requests the async operation to get the games against the A.I. to the HTTP server.
+    pub fn req_details_vs_ai(&mut self) {
+        let request = match self.request.clone().validate() {
+            Ok(request) => request,
+            Err(errors) => {
+                self.field_errors = errors;
+                return;
+            }
+        };
+        self.field_errors = FieldErrors::new();
+        let client = self.client.clone();
+        let fut = async move { client.details_vs_ai(&request).await };
+        #[cfg(target_arch = "wasm32")]
+        log::info!("Requesting details vs A.I.");
+        #[cfg(not(target_arch = "wasm32"))]
+        tracing::info!("Requesting details vs A.I.");
+        self.stats.send(fut);
+    }
+}
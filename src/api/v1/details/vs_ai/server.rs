@@ -0,0 +1,25 @@
+//! Axum route handlers
+
+use super::ListDetailsVsAiReq;
+use crate::common::{DataFrameResponse, ResponseFormat};
+use crate::meta::ResponseMetaBuilder;
+use crate::server::AppState;
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
+
+/// Filters the games against the A.I. based on the query parameters
+pub async fn route_query_vs_ai(
+    headers: HeaderMap,
+    req: Result<Query<ListDetailsVsAiReq>, QueryRejection>,
+    State(state): State<AppState>,
+) -> Result<Response, crate::error::Error> {
+    let Query(req) = req?;
+    tracing::info!("Querying vs A.I.: {:?}", req);
+    let req = req.validate()?;
+    let meta = ResponseMetaBuilder::new();
+    let res = super::dataframe::query_vs_ai(req, state).await?;
+    Ok(DataFrameResponse::new(meta, res, ResponseFormat::from_headers(&headers)).into_response())
+}
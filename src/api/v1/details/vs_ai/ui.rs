@@ -0,0 +1,98 @@
+//! Contains the UI for the games against the A.I.
+
+use crate::app::AppEvent;
+
+use super::{SC2VsAiStats, VsAiStats};
+use eframe::egui;
+use egui::Ui;
+use egui_extras::{Column, TableBuilder};
+
+impl SC2VsAiStats {
+    /// Builds a table for egui with the results per player and A.I. difficulty.
+    fn table_inner(&mut self, ui: &mut Ui, stats: &[VsAiStats]) {
+        let table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::initial(100.0).at_least(40.0).clip(true))
+            .column(Column::initial(120.0).at_least(40.0).clip(true))
+            .column(Column::auto())
+            .column(Column::remainder())
+            .min_scrolled_height(0.0);
+
+        table
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Games");
+                });
+                header.col(|ui| {
+                    ui.strong("Player");
+                });
+                header.col(|ui| {
+                    ui.strong("A.I. Difficulty");
+                });
+                header.col(|ui| {
+                    ui.strong("Wins / Losses");
+                });
+            })
+            .body(|mut body| {
+                let max_games = stats.iter().map(|x| x.count).max().unwrap_or(0);
+                for stat in stats {
+                    body.row(18.0, |mut row| {
+                        row.col(|ui| {
+                            let bar = egui::ProgressBar::new(stat.count as f32 / max_games as f32)
+                                .desired_width(ui.available_width())
+                                .text(stat.count.to_string());
+                            ui.add(bar);
+                        });
+                        row.col(|ui| {
+                            ui.label(&stat.player_name);
+                        });
+                        row.col(|ui| {
+                            ui.label(stat.difficulty_name());
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{} / {}", stat.wins, stat.losses));
+                        });
+                    });
+                }
+            });
+    }
+
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        is_open: &mut bool,
+        _tx: tokio::sync::mpsc::Sender<AppEvent>,
+    ) {
+        egui::Window::new("vs A.I. Statistics")
+            .default_width(480.0)
+            .default_height(480.0)
+            .open(is_open)
+            .show(ctx, |ui| {
+                let field_errors = self.field_errors();
+                if self.request.ui(ui, "vs_ai", &field_errors) {
+                    self.stats.debounce(ctx);
+                }
+                if self.stats.is_due(ctx) {
+                    self.req_details_vs_ai();
+                }
+                self.stats.poll();
+                let stats: Vec<VsAiStats> = match self.stats.result() {
+                    Some(Ok(stats)) => stats.data.clone(),
+                    Some(Err(e)) => {
+                        crate::app::show_api_error(ui, e);
+                        vec![]
+                    }
+                    None => vec![],
+                };
+                crate::app::show_table_heading(
+                    ui,
+                    "Games against the A.I.",
+                    self.stats.is_pending(),
+                );
+                ui.separator();
+                self.table_inner(ui, &stats);
+            });
+    }
+}
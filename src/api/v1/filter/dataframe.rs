@@ -0,0 +1,355 @@
+//! Compiles the replay filters to Polars expressions
+
+use super::{columns, AiPlayers, GameResult, ReplayFilter, SearchMode, TeamLayout};
+use crate::store::{ReplayStore, Table};
+use polars::prelude::*;
+
+/// Case insensitive substring match of a string column
+pub fn contains_ci(column: &str, value: &str) -> Expr {
+    col(column)
+        .str()
+        .to_lowercase()
+        .str()
+        .contains_literal(lit(value.to_lowercase()))
+}
+
+/// Matches a string column with the search mode
+pub fn text_expr(column: &str, value: &str, mode: SearchMode) -> Expr {
+    match mode.pattern(value) {
+        Some(pattern) => col(column).str().contains(lit(pattern), true),
+        None => contains_ci(column, value),
+    }
+}
+
+/// A similarity score of a string column with the value, used to rank the fuzzy matches.
+/// The closer the lengths the higher the score, with a bonus for the prefixes and the
+/// substrings.
+pub fn similarity_expr(column: &str, value: &str) -> Expr {
+    let value = value.to_lowercase();
+    let candidate = col(column).str().to_lowercase();
+    let len = value.chars().count() as f64;
+    let candidate_len = candidate.clone().str().len_chars().cast(DataType::Float64);
+    let length_ratio = lit(len)
+        / when(candidate_len.clone().lt(lit(len.max(1.0))))
+            .then(lit(len.max(1.0)))
+            .otherwise(candidate_len);
+    length_ratio
+        + when(candidate.clone().str().starts_with(lit(value.clone())))
+            .then(lit(1.0))
+            .otherwise(lit(0.0))
+        + when(candidate.str().contains_literal(lit(value)))
+            .then(lit(0.5))
+            .otherwise(lit(0.0))
+}
+
+/// The sortable key of the game version of each replay, see `version_key`.
+pub fn version_key_expr() -> Expr {
+    let parts = col(columns::GAME_VERSION)
+        .cast(DataType::String)
+        .str()
+        .split(lit("."));
+    let part = |index: i64, shift: u32| {
+        parts
+            .clone()
+            .list()
+            .get(lit(index), true)
+            .cast(DataType::UInt64)
+            .fill_null(lit(0u64))
+            * lit(1u64 << shift)
+    };
+    let [major, minor, revision] = super::VERSION_KEY_SHIFTS;
+    part(0, major) + part(1, minor) + part(2, revision)
+}
+
+/// The matchup of each replay as the sorted race initials, i.e. `TvZ`, repeated on every row
+/// of the replay.
+pub fn matchup_expr() -> Expr {
+    col(columns::RACE)
+        .str()
+        .to_uppercase()
+        .str()
+        .head(lit(1))
+        .sort(Default::default())
+        .str()
+        .join("v", true)
+        .over([col(columns::REPLAY_ID)])
+}
+
+/// The number of players of each replay, repeated on every row of the replay.
+pub fn players_per_replay_expr() -> Expr {
+    col(columns::REPLAY_ID)
+        .count()
+        .over([col(columns::REPLAY_ID)])
+}
+
+/// Whether the player had the result, it may be stored as the name or as the numeric value.
+pub fn result_expr(result: GameResult) -> Expr {
+    let player_result = col(columns::RESULT)
+        .cast(DataType::String)
+        .str()
+        .to_lowercase();
+    player_result
+        .clone()
+        .eq(lit(result.name().to_lowercase()))
+        .or(player_result.eq(lit(result.code().to_string())))
+}
+
+/// Whether the row is an A.I. player
+pub fn is_ai_expr() -> Expr {
+    col(columns::CONTROL)
+        .eq(lit(columns::CONTROL_COMPUTER))
+        .fill_null(lit(false))
+}
+
+/// Scans the details with the `slot_observe` and `ai_difficulty` columns of the lobby slots
+/// of the init_data table. The columns are null when the snapshot has no init_data.
+pub fn scan_details_with_slots(store: &dyn ReplayStore) -> Result<LazyFrame, crate::error::Error> {
+    let details = store.scan(Table::Details)?;
+    let init_data = match store.scan(Table::InitData) {
+        Ok(init_data) => init_data,
+        Err(e) => {
+            tracing::debug!(
+                "No lobby slots available, observers can not be excluded: {}",
+                e
+            );
+            return Ok(details.with_columns([
+                lit(NULL).cast(DataType::UInt8).alias(columns::SLOT_OBSERVE),
+                lit(NULL)
+                    .cast(DataType::UInt8)
+                    .alias(columns::AI_DIFFICULTY),
+            ]));
+        }
+    };
+    let slots = init_data.select([
+        col(columns::init_data::REPLAY_ID),
+        col(columns::init_data::WORKING_SET_SLOT_ID),
+        col(columns::init_data::OBSERVE)
+            .cast(DataType::UInt8)
+            .alias(columns::SLOT_OBSERVE),
+        col(columns::init_data::DIFFICULTY)
+            .cast(DataType::UInt8)
+            .alias(columns::AI_DIFFICULTY),
+    ]);
+    Ok(details
+        .join(
+            slots,
+            [col(columns::REPLAY_ID), col(columns::WORKING_SET_SLOT_ID)],
+            [
+                col(columns::init_data::REPLAY_ID),
+                col(columns::init_data::WORKING_SET_SLOT_ID),
+            ],
+            JoinArgs::new(JoinType::Left),
+        )
+        .with_column(
+            when(is_ai_expr())
+                .then(col(columns::AI_DIFFICULTY))
+                .otherwise(lit(NULL).cast(DataType::UInt8))
+                .alias(columns::AI_DIFFICULTY),
+        ))
+}
+
+/// Adds the `team_layout` column to the details, the layout of the teams of each replay,
+/// see `TeamLayout`.
+pub fn with_team_layout(details: LazyFrame) -> LazyFrame {
+    let team_size = col(columns::REPLAY_ID)
+        .count()
+        .over([col(columns::REPLAY_ID), col(columns::TEAM_ID)]);
+    details
+        .with_columns([
+            team_size.alias("__team_size"),
+            players_per_replay_expr().alias("__players"),
+            col(columns::TEAM_ID)
+                .n_unique()
+                .over([col(columns::REPLAY_ID)])
+                .alias("__teams"),
+            col(columns::CONTROL)
+                .eq(lit(columns::CONTROL_COMPUTER))
+                .any(true)
+                .over([col(columns::REPLAY_ID)])
+                .alias("__has_ai"),
+        ])
+        .with_columns([
+            col("__team_size")
+                .min()
+                .over([col(columns::REPLAY_ID)])
+                .alias("__min_team_size"),
+            col("__team_size")
+                .max()
+                .over([col(columns::REPLAY_ID)])
+                .alias("__max_team_size"),
+        ])
+        .with_column(
+            when(col("__has_ai"))
+                .then(lit(TeamLayout::VsAi.name()))
+                .when(
+                    col("__teams")
+                        .eq(lit(2u32))
+                        .and(col("__min_team_size").eq(col("__max_team_size")))
+                        .and(col("__min_team_size").lt_eq(lit(4u32))),
+                )
+                .then(concat_str(
+                    [
+                        col("__min_team_size").cast(DataType::String),
+                        lit("v"),
+                        col("__min_team_size").cast(DataType::String),
+                    ],
+                    "",
+                    true,
+                ))
+                .when(
+                    col("__players")
+                        .gt(lit(2u32))
+                        .and(col("__teams").eq(col("__players"))),
+                )
+                .then(lit(TeamLayout::FreeForAll.name()))
+                .otherwise(lit(TeamLayout::Other.name()))
+                .alias(columns::TEAM_LAYOUT),
+        )
+        .drop([
+            "__team_size",
+            "__players",
+            "__teams",
+            "__has_ai",
+            "__min_team_size",
+            "__max_team_size",
+        ])
+}
+
+impl ReplayFilter {
+    /// The filters that select whole replays. These must be applied before the player filters
+    /// as some of them look at all the players of the replay, see `filter_replays`.
+    pub fn replay_expr(&self) -> Expr {
+        let mut expr = col(columns::DATETIME)
+            .gt(lit(self.file_min_date))
+            .and(col(columns::DATETIME).lt(lit(self.file_max_date)));
+        if !self.title.is_empty() {
+            expr = expr.and(text_expr(columns::TITLE, &self.title, self.search_mode));
+        }
+        if !self.file_name.is_empty() {
+            expr = expr.and(text_expr(
+                columns::FILE_NAME,
+                &self.file_name,
+                self.search_mode,
+            ));
+        }
+        if let Ok(replay_id) = self.replay_id.parse::<u64>() {
+            expr = expr.and(col(columns::REPLAY_ID).eq(lit(replay_id)));
+        }
+        if !self.matchup.is_empty() {
+            expr = expr.and(matchup_expr().eq(lit(self.matchup.clone())));
+        }
+        if !self.game_version.is_empty() {
+            expr = expr.and(
+                col(columns::GAME_VERSION)
+                    .cast(DataType::String)
+                    .str()
+                    .starts_with(lit(self.game_version.clone())),
+            );
+        }
+        if let Some(min_version) = super::version_key(&self.min_version) {
+            expr = expr.and(version_key_expr().gt_eq(lit(min_version)));
+        }
+        if let Some(max_version) = super::version_key(&self.max_version) {
+            expr = expr.and(version_key_expr().lt_eq(lit(max_version)));
+        }
+        if let Some(region) = self.region {
+            expr = expr.and(col(columns::REGION).eq(lit(region.code())));
+        }
+        if let Some(min_duration) = self.min_duration {
+            expr = expr.and(col(columns::DURATION).gt_eq(lit(min_duration)));
+        }
+        if let Some(max_duration) = self.max_duration {
+            expr = expr.and(col(columns::DURATION).lt_eq(lit(max_duration)));
+        }
+        if let Some(layout) = self.layout {
+            expr = expr.and(col(columns::TEAM_LAYOUT).eq(lit(layout.name())));
+        }
+        expr
+    }
+
+    /// Selects the rows of the lobby slots that take part in the game, the observers and
+    /// referees are excluded unless requested. This must be applied before the replay filters
+    /// so that they are not counted in the team layout.
+    pub fn slot_expr(&self) -> Expr {
+        if self.include_observers {
+            lit(true)
+        } else {
+            col(columns::SLOT_OBSERVE).fill_null(lit(0u8)).eq(lit(0u8))
+        }
+    }
+
+    /// The filter that selects the rows of the A.I. players or of the humans.
+    pub fn ai_expr(&self) -> Expr {
+        match self.ai {
+            AiPlayers::Exclude => is_ai_expr().not(),
+            AiPlayers::Include => lit(true),
+            AiPlayers::Only => is_ai_expr(),
+        }
+    }
+
+    /// The filters that select the rows of the players.
+    pub fn player_expr(&self) -> Expr {
+        let mut expr = self.ai_expr();
+        if !self.player.is_empty() {
+            expr = expr.and(text_expr(
+                columns::PLAYER_NAME,
+                &self.player,
+                self.search_mode,
+            ));
+        }
+        if let Some(race) = self.race {
+            expr = expr.and(contains_ci(columns::RACE, race.name()));
+        }
+        if let Some(result) = self.result {
+            expr = expr.and(result_expr(result));
+        }
+        expr
+    }
+
+    /// Applies the slot and the replay filters, the `team_layout` column is only added when
+    /// filtering by layout as it needs every player of the replay.
+    /// The details must have been scanned with `scan_details_with_slots`.
+    pub fn filter_replays(&self, details: LazyFrame) -> LazyFrame {
+        let details = details.filter(self.slot_expr());
+        let details = if self.layout.is_some() {
+            with_team_layout(details)
+        } else {
+            details
+        };
+        details.filter(self.replay_expr())
+    }
+
+    /// Sorts the results by frequency, the fuzzy matches are ranked by their similarity with
+    /// the value first.
+    pub fn sort_by_relevance(&self, lf: LazyFrame, column: &str, value: &str) -> LazyFrame {
+        if self.search_mode == SearchMode::Fuzzy && !value.is_empty() {
+            lf.sort_by_exprs(
+                [similarity_expr(column, value), col("count")],
+                SortMultipleOptions {
+                    descending: vec![true, true],
+                    ..Default::default()
+                },
+            )
+        } else {
+            lf.sort(
+                ["count"],
+                SortMultipleOptions {
+                    descending: vec![true],
+                    ..Default::default()
+                },
+            )
+        }
+    }
+
+    /// Applies the replay and the player filters
+    pub fn apply(&self, details: LazyFrame) -> LazyFrame {
+        self.filter_replays(details).filter(self.player_expr())
+    }
+
+    /// The ids of the replays matching the filters, to be semi joined with other tables.
+    pub fn replay_ids(&self, details: LazyFrame) -> LazyFrame {
+        self.apply(details)
+            .select([col(columns::REPLAY_ID)])
+            .unique(None, UniqueKeepStrategy::Any)
+    }
+}
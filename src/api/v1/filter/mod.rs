@@ -0,0 +1,734 @@
+//! The replay filters shared by every endpoint
+//!
+//! The filters are compiled to a Polars `Expr` over the `details` table, so that
+//! all the endpoints filter the replays identically.
+
+pub mod ui;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+use crate::api::validation::{check_date_range, normalize_replay_id, normalize_text, FieldErrors};
+use serde::{Deserialize, Serialize};
+
+/// The columns of the details table used by the filters.
+pub mod columns {
+    /// The snapshot replay id
+    pub const REPLAY_ID: &str = "ext_fs_id";
+    /// The date of the replay file
+    pub const DATETIME: &str = "ext_datetime";
+    /// The path of the replay file
+    pub const FILE_NAME: &str = "ext_fs_replay_file_name";
+    /// The map title
+    pub const TITLE: &str = "title";
+    /// The name of the player, the clan is prefixed and separated by `<sp/>`
+    pub const PLAYER_NAME: &str = "player_name";
+    /// The race of the player
+    pub const RACE: &str = "player_race";
+    /// The result of the player
+    pub const RESULT: &str = "player_result";
+    /// The battle.net region of the player
+    pub const REGION: &str = "player_toon_region";
+    /// The version of the game that recorded the replay, i.e. `5.0.12.92440`
+    pub const GAME_VERSION: &str = "ext_replay_version";
+    /// The base build of the game that recorded the replay
+    pub const BASE_BUILD: &str = "ext_replay_base_build";
+    /// The duration of the replay in seconds
+    pub const DURATION: &str = "ext_replay_seconds";
+    /// The team of the player
+    pub const TEAM_ID: &str = "player_team_id";
+    /// Whether the player is a human or the computer, see `CONTROL_COMPUTER`
+    pub const CONTROL: &str = "player_control";
+    /// The layout of the teams of the replay, derived from the other columns
+    pub const TEAM_LAYOUT: &str = "team_layout";
+
+    /// The lobby slot of the player, to join with the `init_data` slots
+    pub const WORKING_SET_SLOT_ID: &str = "player_working_set_slot_id";
+    /// Whether the slot is an observer or a referee, from the `init_data` slots
+    pub const SLOT_OBSERVE: &str = "slot_observe";
+    /// The difficulty of the A.I., from the `init_data` slots
+    pub const AI_DIFFICULTY: &str = "ai_difficulty";
+
+    /// The value of `CONTROL` for the A.I. players
+    pub const CONTROL_COMPUTER: u8 = 3;
+
+    /// The columns of the lobby slots of the init_data table.
+    pub mod init_data {
+        /// The snapshot replay id
+        pub const REPLAY_ID: &str = "ext_fs_id";
+        /// The slot id, matches the `player_working_set_slot_id` of the details
+        pub const WORKING_SET_SLOT_ID: &str = "working_set_slot_id";
+        /// 0 for the players, 1 for the observers and 2 for the referees
+        pub const OBSERVE: &str = "observe";
+        /// The difficulty of the A.I. slots
+        pub const DIFFICULTY: &str = "difficulty";
+    }
+}
+
+/// The name of an A.I. difficulty as shown in the game lobby
+pub fn ai_difficulty_name(difficulty: u8) -> &'static str {
+    match difficulty {
+        1 => "Very Easy",
+        2 => "Easy",
+        3 => "Medium",
+        4 => "Hard",
+        5 => "Harder",
+        6 => "Very Hard",
+        7 => "Elite",
+        8 => "Cheater 1 (Vision)",
+        9 => "Cheater 2 (Resources)",
+        10 => "Cheater 3 (Insane)",
+        _ => "Unknown",
+    }
+}
+
+/// The sortable key of a version, its major, minor and revision numbers in 16 bits each, so
+/// that `5.0.9` is before `5.0.11` and `4.0.100` is before `4.1.0`. The build number is
+/// ignored.
+pub fn version_key(version: &str) -> Option<u64> {
+    let mut key = 0;
+    let mut parts = version.split('.');
+    for shift in VERSION_KEY_SHIFTS {
+        let part = match parts.next() {
+            Some(part) => part.parse::<u16>().ok()?,
+            None => 0,
+        };
+        key |= u64::from(part) << shift;
+    }
+    Some(key)
+}
+
+/// The bit offsets of the major, minor and revision numbers in a version key
+pub const VERSION_KEY_SHIFTS: [u32; 3] = [32, 16, 0];
+
+/// A named range of versions, i.e. the balance patches from `5.0.11` to `5.0.12`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PatchEra {
+    /// The name of the era
+    pub name: String,
+    /// The first version of the era
+    pub min_version: String,
+    /// The last version of the era, inclusive
+    pub max_version: String,
+}
+
+/// The battle.net regions of the toons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Region {
+    /// The Americas
+    Us,
+    /// Europe
+    Eu,
+    /// Korea and Taiwan
+    Kr,
+    /// China
+    Cn,
+    /// The public test realm
+    Ptr,
+}
+
+impl Region {
+    /// All the regions, for dropdowns
+    pub fn all() -> &'static [Region] {
+        &[Self::Us, Self::Eu, Self::Kr, Self::Cn, Self::Ptr]
+    }
+
+    /// The region of the numeric `player_toon_region`
+    pub fn from_code(code: u64) -> Option<Self> {
+        match code {
+            1 => Some(Self::Us),
+            2 => Some(Self::Eu),
+            3 => Some(Self::Kr),
+            5 => Some(Self::Cn),
+            98 => Some(Self::Ptr),
+            _ => None,
+        }
+    }
+
+    /// The numeric value of the region in the toons
+    pub fn code(&self) -> u64 {
+        match self {
+            Self::Us => 1,
+            Self::Eu => 2,
+            Self::Kr => 3,
+            Self::Cn => 5,
+            Self::Ptr => 98,
+        }
+    }
+
+    /// The short name of the region
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Us => "US",
+            Self::Eu => "EU",
+            Self::Kr => "KR",
+            Self::Cn => "CN",
+            Self::Ptr => "PTR",
+        }
+    }
+
+    /// The flag of the region
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Self::Us => "🇺🇸",
+            Self::Eu => "🇪🇺",
+            Self::Kr => "🇰🇷",
+            Self::Cn => "🇨🇳",
+            Self::Ptr => "🧪",
+        }
+    }
+
+    /// The flag and the name of the region
+    pub fn label(&self) -> String {
+        format!("{} {}", self.flag(), self.name())
+    }
+}
+
+impl TryFrom<String> for Region {
+    type Error = String;
+
+    /// Parses the name of the region, i.e. `eu`, or its numeric value, i.e. `2`
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Ok(code) = value.parse::<u64>() {
+            return Self::from_code(code).ok_or_else(|| format!("Unknown region: {}", value));
+        }
+        Self::all()
+            .iter()
+            .find(|region| region.name().eq_ignore_ascii_case(&value))
+            .copied()
+            .ok_or_else(|| format!("Unknown region: {}", value))
+    }
+}
+
+impl From<Region> for String {
+    fn from(region: Region) -> Self {
+        region.name().to_lowercase()
+    }
+}
+
+/// The playable races
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Race {
+    Protoss,
+    Terran,
+    Zerg,
+    Random,
+}
+
+impl Race {
+    /// All the races, for dropdowns
+    pub fn all() -> &'static [Race] {
+        &[Self::Protoss, Self::Terran, Self::Zerg, Self::Random]
+    }
+
+    /// The name of the race as stored in the snapshot
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Protoss => "Protoss",
+            Self::Terran => "Terran",
+            Self::Zerg => "Zerg",
+            Self::Random => "Random",
+        }
+    }
+}
+
+/// The result of a player in a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameResult {
+    Win,
+    Loss,
+    Tie,
+    Undecided,
+}
+
+impl GameResult {
+    /// All the results, for dropdowns
+    pub fn all() -> &'static [GameResult] {
+        &[Self::Win, Self::Loss, Self::Tie, Self::Undecided]
+    }
+
+    /// The name of the result as stored in the snapshot
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Win => "Win",
+            Self::Loss => "Loss",
+            Self::Tie => "Tie",
+            Self::Undecided => "Undecided",
+        }
+    }
+
+    /// The numeric value of the result in the replay details
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Undecided => 0,
+            Self::Win => 1,
+            Self::Loss => 2,
+            Self::Tie => 3,
+        }
+    }
+}
+
+/// How the free text filters are matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Case insensitive substring
+    #[default]
+    Substring,
+    /// Case insensitive regular expression
+    Regex,
+    /// The characters appear in order, the results are ranked by similarity
+    Fuzzy,
+}
+
+impl SearchMode {
+    /// All the modes, for dropdowns
+    pub fn all() -> &'static [SearchMode] {
+        &[Self::Substring, Self::Regex, Self::Fuzzy]
+    }
+
+    /// The label of the mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Substring => "Substring",
+            Self::Regex => "Regex",
+            Self::Fuzzy => "Fuzzy",
+        }
+    }
+
+    /// The case insensitive regular expression for a value, `None` for the substrings
+    /// which are matched literally.
+    pub fn pattern(&self, value: &str) -> Option<String> {
+        match self {
+            Self::Substring => None,
+            Self::Regex => Some(format!("(?i){}", value)),
+            Self::Fuzzy => {
+                let chars: Vec<String> = value
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .map(|c| regex::escape(&c.to_string()))
+                    .collect();
+                Some(format!("(?i){}", chars.join(".*")))
+            }
+        }
+    }
+
+    /// Checks the value is a valid regular expression in the regex mode
+    pub fn check(&self, field: &str, value: &str, errors: &mut FieldErrors) {
+        if *self == Self::Regex && !value.is_empty() {
+            if let Err(e) = regex::Regex::new(value) {
+                errors.add(field, format!("Invalid regular expression: {}", e));
+            }
+        }
+    }
+}
+
+/// Whether the A.I. players are included in the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiPlayers {
+    /// Only the human players
+    #[default]
+    Exclude,
+    /// Both the human and the A.I. players
+    Include,
+    /// Only the A.I. players
+    Only,
+}
+
+impl AiPlayers {
+    /// All the options, for dropdowns
+    pub fn all() -> &'static [AiPlayers] {
+        &[Self::Exclude, Self::Include, Self::Only]
+    }
+
+    /// The label of the option
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Exclude => "Humans",
+            Self::Include => "Humans and A.I.",
+            Self::Only => "A.I. only",
+        }
+    }
+}
+
+/// The layout of the teams of a replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TeamLayout {
+    #[serde(rename = "1v1")]
+    OneVsOne,
+    #[serde(rename = "2v2")]
+    TwoVsTwo,
+    #[serde(rename = "3v3")]
+    ThreeVsThree,
+    #[serde(rename = "4v4")]
+    FourVsFour,
+    /// Every player on its own team, with more than two players
+    #[serde(rename = "ffa")]
+    FreeForAll,
+    /// At least one of the players is the computer
+    #[serde(rename = "vs_ai")]
+    VsAi,
+    /// Uneven teams or custom games
+    #[serde(rename = "other")]
+    Other,
+}
+
+impl TeamLayout {
+    /// All the layouts, for dropdowns
+    pub fn all() -> &'static [TeamLayout] {
+        &[
+            Self::OneVsOne,
+            Self::TwoVsTwo,
+            Self::ThreeVsThree,
+            Self::FourVsFour,
+            Self::FreeForAll,
+            Self::VsAi,
+            Self::Other,
+        ]
+    }
+
+    /// The name of the layout, as stored in the `team_layout` column
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::OneVsOne => "1v1",
+            Self::TwoVsTwo => "2v2",
+            Self::ThreeVsThree => "3v3",
+            Self::FourVsFour => "4v4",
+            Self::FreeForAll => "ffa",
+            Self::VsAi => "vs_ai",
+            Self::Other => "other",
+        }
+    }
+
+    /// Whether the players of the layout play with partners
+    pub fn is_team_game(&self) -> bool {
+        matches!(self, Self::TwoVsTwo | Self::ThreeVsThree | Self::FourVsFour)
+    }
+}
+
+/// Deserializes the numbers and booleans from their string form too. The query strings only
+/// carry strings, serde converts them on the fly but not once they are buffered for a
+/// `#[serde(flatten)]` field, i.e. the filter of the tracker events requests.
+mod from_str {
+    use serde::{de::Error, Deserialize, Deserializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrOrValue<T> {
+        Str(String),
+        Value(T),
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr + Deserialize<'de>,
+        T::Err: Display,
+    {
+        match StrOrValue::<T>::deserialize(deserializer)? {
+            StrOrValue::Str(value) => value.parse().map_err(D::Error::custom),
+            StrOrValue::Value(value) => Ok(value),
+        }
+    }
+
+    /// The empty strings are `None`
+    pub mod option {
+        use super::*;
+
+        pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            D: Deserializer<'de>,
+            T: FromStr + Deserialize<'de>,
+            T::Err: Display,
+        {
+            match Option::<StrOrValue<T>>::deserialize(deserializer)? {
+                None => Ok(None),
+                Some(StrOrValue::Str(value)) if value.is_empty() => Ok(None),
+                Some(StrOrValue::Str(value)) => value.parse().map(Some).map_err(D::Error::custom),
+                Some(StrOrValue::Value(value)) => Ok(Some(value)),
+            }
+        }
+    }
+}
+
+/// The filters available on the replays, every field is optional.
+/// The replay level filters select whole games, the player level filters
+/// (player name, race and result) select the rows of the matching players.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReplayFilter {
+    /// The title of the map
+    #[serde(default)]
+    pub title: String,
+    /// A player that must have played in the game
+    #[serde(default, alias = "name")]
+    pub player: String,
+    /// Part of the file name
+    #[serde(default)]
+    pub file_name: String,
+    /// The snapshot replay id
+    #[serde(default)]
+    pub replay_id: String,
+    /// Minimum bound of the file date
+    #[serde(default = "ReplayFilter::default_min_date")]
+    pub file_min_date: chrono::NaiveDate,
+    /// Max bound of the file date
+    #[serde(default = "ReplayFilter::default_max_date")]
+    pub file_max_date: chrono::NaiveDate,
+    /// The race of the player
+    #[serde(default)]
+    pub race: Option<Race>,
+    /// The result of the player
+    #[serde(default)]
+    pub result: Option<GameResult>,
+    /// The races of the game, i.e. `TvZ`, the order does not matter
+    #[serde(default)]
+    pub matchup: String,
+    /// The prefix of the game version, i.e. `5.0.12`
+    #[serde(default)]
+    pub game_version: String,
+    /// The first version, inclusive, i.e. `5.0.11`
+    #[serde(default)]
+    pub min_version: String,
+    /// The last version, inclusive, i.e. `5.0.12`
+    #[serde(default)]
+    pub max_version: String,
+    /// The battle.net region of the players, i.e. `eu`
+    #[serde(default)]
+    pub region: Option<Region>,
+    /// The minimum duration of the game in seconds
+    #[serde(default, deserialize_with = "from_str::option::deserialize")]
+    pub min_duration: Option<u32>,
+    /// The maximum duration of the game in seconds
+    #[serde(default, deserialize_with = "from_str::option::deserialize")]
+    pub max_duration: Option<u32>,
+    /// The layout of the teams, i.e. only 1v1 games
+    #[serde(default)]
+    pub layout: Option<TeamLayout>,
+    /// Whether the A.I. players are included
+    #[serde(default)]
+    pub ai: AiPlayers,
+    /// Whether the observers and referees are included
+    #[serde(default, deserialize_with = "from_str::deserialize")]
+    pub include_observers: bool,
+    /// How the title, player and file name filters are matched
+    #[serde(default)]
+    pub search_mode: SearchMode,
+}
+
+impl Default for ReplayFilter {
+    fn default() -> Self {
+        Self {
+            title: Default::default(),
+            player: Default::default(),
+            file_name: Default::default(),
+            replay_id: Default::default(),
+            file_min_date: Self::default_min_date(),
+            file_max_date: Self::default_max_date(),
+            race: None,
+            result: None,
+            matchup: Default::default(),
+            game_version: Default::default(),
+            min_version: Default::default(),
+            max_version: Default::default(),
+            region: None,
+            min_duration: None,
+            max_duration: None,
+            layout: None,
+            ai: AiPlayers::Exclude,
+            include_observers: false,
+            search_mode: SearchMode::Substring,
+        }
+    }
+}
+
+impl ReplayFilter {
+    /// Normalizes the filters and rejects invalid combinations.
+    pub fn validate(self) -> Result<Self, FieldErrors> {
+        let mut errors = FieldErrors::new();
+        let req = Self {
+            title: normalize_text("title", self.title, &mut errors),
+            player: normalize_text("player", self.player, &mut errors),
+            file_name: normalize_text("file_name", self.file_name, &mut errors),
+            replay_id: normalize_replay_id("replay_id", self.replay_id, &mut errors),
+            matchup: normalize_matchup("matchup", self.matchup, &mut errors),
+            game_version: normalize_text("game_version", self.game_version, &mut errors),
+            min_version: normalize_text("min_version", self.min_version, &mut errors),
+            max_version: normalize_text("max_version", self.max_version, &mut errors),
+            ..self
+        };
+        check_date_range(
+            "file_min_date",
+            req.file_min_date,
+            "file_max_date",
+            req.file_max_date,
+            &mut errors,
+        );
+        for (field, value) in [
+            ("title", &req.title),
+            ("player", &req.player),
+            ("file_name", &req.file_name),
+        ] {
+            req.search_mode.check(field, value, &mut errors);
+        }
+        if let (Some(min_duration), Some(max_duration)) = (req.min_duration, req.max_duration) {
+            if min_duration > max_duration {
+                errors.add("min_duration", "Must be less than the max_duration");
+                errors.add("max_duration", "Must be more than the min_duration");
+            }
+        }
+        if !req
+            .game_version
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.')
+        {
+            errors.add("game_version", "Must be a version, i.e. 5.0.12");
+        }
+        let min_version = check_version("min_version", &req.min_version, &mut errors);
+        let max_version = check_version("max_version", &req.max_version, &mut errors);
+        if let (Some(min_version), Some(max_version)) = (min_version, max_version) {
+            if min_version > max_version {
+                errors.add("min_version", "Must be before the max_version");
+                errors.add("max_version", "Must be after the min_version");
+            }
+        }
+        errors.into_result(req)
+    }
+
+    /// Creates a default min date for dropdowns on date filters.
+    pub fn default_min_date() -> chrono::NaiveDate {
+        chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()
+    }
+
+    /// Creates a default max date for dropdowns on date filters.
+    pub fn default_max_date() -> chrono::NaiveDate {
+        chrono::Local::now().naive_local().date()
+    }
+}
+
+/// Checks an optional version bound, returns its key when set.
+fn check_version(field: &str, value: &str, errors: &mut FieldErrors) -> Option<u64> {
+    if value.is_empty() {
+        return None;
+    }
+    let key = version_key(value);
+    if key.is_none() {
+        errors.add(field, "Must be a version, i.e. 5.0.12");
+    }
+    key
+}
+
+/// Normalizes a matchup to the sorted, uppercase race initials, i.e. `zvt` is `TvZ`.
+pub fn normalize_matchup(field: &str, value: String, errors: &mut FieldErrors) -> String {
+    let value = normalize_text(field, value, errors);
+    if value.is_empty() {
+        return value;
+    }
+    let mut races: Vec<String> = value
+        .split(['v', 'V'])
+        .map(|race| race.trim().to_uppercase())
+        .collect();
+    if races.len() < 2
+        || races
+            .iter()
+            .any(|race| !["P", "T", "Z", "R"].contains(&race.as_str()))
+    {
+        errors.add(field, "Must be race initials separated by v, i.e. TvZ");
+        return value;
+    }
+    races.sort();
+    races.join("v")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_replay_filter() {
+        let filter = ReplayFilter {
+            title: " Alcyone ".to_string(),
+            matchup: "zvt".to_string(),
+            ..Default::default()
+        }
+        .validate()
+        .unwrap();
+        assert_eq!(filter.title, "Alcyone");
+        assert_eq!(filter.matchup, "TvZ");
+        let errors = ReplayFilter {
+            matchup: "ZvX".to_string(),
+            min_duration: Some(600),
+            max_duration: Some(60),
+            ..Default::default()
+        }
+        .validate()
+        .unwrap_err();
+        assert!(errors.get("matchup").is_some());
+        assert!(errors.get("min_duration").is_some());
+        let errors = ReplayFilter {
+            player: "(serral".to_string(),
+            search_mode: SearchMode::Regex,
+            ..Default::default()
+        }
+        .validate()
+        .unwrap_err();
+        assert!(errors.get("player").is_some());
+    }
+
+    #[test]
+    fn test_version_range() {
+        assert!(version_key("5.0.9") < version_key("5.0.11"));
+        assert_eq!(version_key("5.0.12.92440"), version_key("5.0.12"));
+        assert_eq!(version_key("5.x"), None);
+        assert!(version_key("4.0.100") < version_key("4.1.0"));
+        assert!(version_key("4.99.0") < version_key("5.0.0"));
+        assert_eq!(version_key("5.0.70000"), None);
+        let errors = ReplayFilter {
+            min_version: "5.0.12".to_string(),
+            max_version: "5.0.11".to_string(),
+            ..Default::default()
+        }
+        .validate()
+        .unwrap_err();
+        assert!(errors.get("min_version").is_some());
+    }
+
+    #[test]
+    fn test_search_mode_pattern() {
+        assert_eq!(SearchMode::Substring.pattern("serral"), None);
+        assert_eq!(
+            SearchMode::Fuzzy.pattern("s.l").as_deref(),
+            Some("(?i)s.*\\..*l")
+        );
+        let fuzzy = regex::Regex::new(&SearchMode::Fuzzy.pattern("srl").unwrap()).unwrap();
+        assert!(fuzzy.is_match("Serral"));
+        assert!(!fuzzy.is_match("Maru"));
+    }
+
+    #[test]
+    fn test_replay_filter_query_string() {
+        let filter: ReplayFilter =
+            serde_urlencoded::from_str("name=serral&race=zerg&layout=1v1&min_duration=60&ai=only")
+                .unwrap();
+        assert_eq!(filter.player, "serral");
+        assert_eq!(filter.race, Some(Race::Zerg));
+        assert_eq!(filter.layout, Some(TeamLayout::OneVsOne));
+        assert_eq!(filter.ai, AiPlayers::Only);
+        assert_eq!(filter.region, None);
+        let filter: ReplayFilter = serde_urlencoded::from_str("region=eu").unwrap();
+        assert_eq!(filter.region, Some(Region::Eu));
+        let filter: ReplayFilter = serde_urlencoded::from_str("region=3").unwrap();
+        assert_eq!(filter.region, Some(Region::Kr));
+        assert!(serde_urlencoded::to_string(&filter)
+            .unwrap()
+            .contains("region=kr"));
+        assert!(!filter.include_observers);
+        assert_eq!(filter.min_duration, Some(60));
+        assert_eq!(filter.file_min_date, ReplayFilter::default_min_date());
+    }
+}
@@ -0,0 +1,232 @@
+//! The filter bar shared by the windows that query replays.
+
+use super::{AiPlayers, GameResult, PatchEra, Race, Region, ReplayFilter, SearchMode, TeamLayout};
+use crate::api::validation::FieldErrors;
+use crate::app::show_field_error;
+use eframe::egui;
+use egui::Ui;
+use egui::Widget;
+use egui_extras::DatePickerButton;
+
+/// Shows a checkbox to enable the value and a DragValue to edit it, returns whether it changed.
+fn optional_drag_value(ui: &mut Ui, label: &str, value: &mut Option<u32>, default: u32) -> bool {
+    let mut enabled = value.is_some();
+    let mut changed = ui.checkbox(&mut enabled, label).changed();
+    if changed {
+        *value = if enabled { Some(default) } else { None };
+    }
+    if let Some(inner) = value {
+        changed |= ui
+            .add(egui::DragValue::new(inner).suffix(" s").speed(10.0))
+            .changed();
+    }
+    changed
+}
+
+/// The id of the patch eras in the egui memory, they are shared by all the filter bars and
+/// persisted with the app.
+fn patch_eras_id() -> egui::Id {
+    egui::Id::new("patch_eras")
+}
+
+/// Lists the patch eras to apply one as the version range and a form to define new ones,
+/// returns whether the filter changed.
+fn patch_eras_ui(ui: &mut Ui, filter: &mut ReplayFilter) -> bool {
+    let mut changed = false;
+    let mut eras: Vec<PatchEra> = ui.data_mut(|data| {
+        data.get_persisted_mut_or_default::<Vec<PatchEra>>(patch_eras_id())
+            .clone()
+    });
+    let mut new_era: PatchEra =
+        ui.data_mut(|data| data.get_temp(patch_eras_id()).unwrap_or_default());
+    let mut eras_changed = false;
+    ui.menu_button("Patch eras", |ui| {
+        if ui.button("All versions").clicked() {
+            filter.min_version.clear();
+            filter.max_version.clear();
+            changed = true;
+            ui.close_menu();
+        }
+        let mut remove = None;
+        for (idx, era) in eras.iter().enumerate() {
+            ui.horizontal(|ui| {
+                let label = format!("{} ({} - {})", era.name, era.min_version, era.max_version);
+                if ui.button(label).clicked() {
+                    filter.min_version = era.min_version.clone();
+                    filter.max_version = era.max_version.clone();
+                    changed = true;
+                    ui.close_menu();
+                }
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(idx);
+                }
+            });
+        }
+        if let Some(idx) = remove {
+            eras.remove(idx);
+            eras_changed = true;
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            egui::TextEdit::singleline(&mut new_era.name)
+                .hint_text("Name")
+                .desired_width(80.0)
+                .ui(ui);
+            egui::TextEdit::singleline(&mut new_era.min_version)
+                .hint_text("5.0.11")
+                .desired_width(50.0)
+                .ui(ui);
+            egui::TextEdit::singleline(&mut new_era.max_version)
+                .hint_text("5.0.12")
+                .desired_width(50.0)
+                .ui(ui);
+            let is_valid = !new_era.name.trim().is_empty()
+                && super::version_key(&new_era.min_version).is_some()
+                && super::version_key(&new_era.max_version).is_some();
+            if ui.add_enabled(is_valid, egui::Button::new("Add")).clicked() {
+                eras.push(std::mem::take(&mut new_era));
+                eras_changed = true;
+            }
+        });
+    });
+    ui.data_mut(|data| {
+        data.insert_temp(patch_eras_id(), new_era);
+        if eras_changed {
+            data.insert_persisted(patch_eras_id(), eras);
+        }
+    });
+    changed
+}
+
+impl ReplayFilter {
+    /// Builds the filter bar, returns true when any of the filters changed.
+    pub fn ui(&mut self, ui: &mut Ui, id_salt: &str, errors: &FieldErrors) -> bool {
+        let mut changed = false;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Filters > ");
+            egui::ComboBox::from_id_salt(format!("{}_search_mode", id_salt))
+                .selected_text(self.search_mode.name())
+                .width(80.0)
+                .show_ui(ui, |ui| {
+                    for mode in SearchMode::all() {
+                        changed |= ui
+                            .selectable_value(&mut self.search_mode, *mode, mode.name())
+                            .changed();
+                    }
+                });
+            ui.label("Map: ");
+            changed |= ui.text_edit_singleline(&mut self.title).changed();
+            show_field_error(ui, errors, "title");
+            ui.label("Player: ");
+            changed |= ui.text_edit_singleline(&mut self.player).changed();
+            show_field_error(ui, errors, "player");
+            ui.label("File Path: ");
+            changed |= ui.text_edit_singleline(&mut self.file_name).changed();
+            show_field_error(ui, errors, "file_name");
+            ui.label("Snapshot Replay ID: ");
+            changed |= ui.text_edit_singleline(&mut self.replay_id).changed();
+            show_field_error(ui, errors, "replay_id");
+            ui.label("Min date: ");
+            changed |= DatePickerButton::new(&mut self.file_min_date)
+                .id_salt(&format!("{}_min_date", id_salt))
+                .ui(ui)
+                .changed();
+            show_field_error(ui, errors, "file_min_date");
+            ui.label("Max date: ");
+            changed |= DatePickerButton::new(&mut self.file_max_date)
+                .id_salt(&format!("{}_max_date", id_salt))
+                .ui(ui)
+                .changed();
+            show_field_error(ui, errors, "file_max_date");
+        });
+        ui.horizontal_wrapped(|ui| {
+            egui::ComboBox::from_label("Race")
+                .selected_text(self.race.map_or("Any", |race| race.name()))
+                .show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(&mut self.race, None, "Any").changed();
+                    for race in Race::all() {
+                        changed |= ui
+                            .selectable_value(&mut self.race, Some(*race), race.name())
+                            .changed();
+                    }
+                });
+            egui::ComboBox::from_label("Result")
+                .selected_text(self.result.map_or("Any", |result| result.name()))
+                .show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(&mut self.result, None, "Any").changed();
+                    for result in GameResult::all() {
+                        changed |= ui
+                            .selectable_value(&mut self.result, Some(*result), result.name())
+                            .changed();
+                    }
+                });
+            ui.label("Matchup: ");
+            changed |= egui::TextEdit::singleline(&mut self.matchup)
+                .hint_text("TvZ")
+                .desired_width(40.0)
+                .ui(ui)
+                .changed();
+            show_field_error(ui, errors, "matchup");
+            ui.label("Version: ");
+            changed |= egui::TextEdit::singleline(&mut self.game_version)
+                .hint_text("5.0.12")
+                .desired_width(60.0)
+                .ui(ui)
+                .changed();
+            show_field_error(ui, errors, "game_version");
+            ui.label("From: ");
+            changed |= egui::TextEdit::singleline(&mut self.min_version)
+                .hint_text("5.0.11")
+                .desired_width(50.0)
+                .ui(ui)
+                .changed();
+            show_field_error(ui, errors, "min_version");
+            ui.label("To: ");
+            changed |= egui::TextEdit::singleline(&mut self.max_version)
+                .hint_text("5.0.12")
+                .desired_width(50.0)
+                .ui(ui)
+                .changed();
+            show_field_error(ui, errors, "max_version");
+            changed |= patch_eras_ui(ui, self);
+            egui::ComboBox::from_label("Region")
+                .selected_text(
+                    self.region
+                        .map_or("Any".to_string(), |region| region.label()),
+                )
+                .show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(&mut self.region, None, "Any").changed();
+                    for region in Region::all() {
+                        changed |= ui
+                            .selectable_value(&mut self.region, Some(*region), region.label())
+                            .changed();
+                    }
+                });
+            changed |= optional_drag_value(ui, "Min duration", &mut self.min_duration, 0);
+            show_field_error(ui, errors, "min_duration");
+            changed |= optional_drag_value(ui, "Max duration", &mut self.max_duration, 1800);
+            show_field_error(ui, errors, "max_duration");
+            egui::ComboBox::from_label("Layout")
+                .selected_text(self.layout.map_or("Any", |layout| layout.name()))
+                .show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(&mut self.layout, None, "Any").changed();
+                    for layout in TeamLayout::all() {
+                        changed |= ui
+                            .selectable_value(&mut self.layout, Some(*layout), layout.name())
+                            .changed();
+                    }
+                });
+            egui::ComboBox::from_label("Players")
+                .selected_text(self.ai.name())
+                .show_ui(ui, |ui| {
+                    for ai in AiPlayers::all() {
+                        changed |= ui.selectable_value(&mut self.ai, *ai, ai.name()).changed();
+                    }
+                });
+            changed |= ui
+                .checkbox(&mut self.include_observers, "Observers")
+                .changed();
+        });
+        changed
+    }
+}
@@ -1,11 +1,18 @@
 //! API V1 routes
 
 pub mod details;
+pub mod filter;
+pub mod search;
 pub mod snapshot_stats;
+pub mod sql;
 pub mod tracker_events;
 
 #[cfg(not(target_arch = "wasm32"))]
-use axum::{extract::State, routing::get, Router};
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Router,
+};
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::server::AppState;
@@ -17,6 +24,8 @@ pub fn routes(state: State<AppState>) -> Router {
             "/snapshot_stats",
             get(snapshot_stats::server::route_snapshot_stats),
         )
+        .route("/search", get(search::server::route_search))
+        .route("/sql", post(sql::server::route_query_sql))
         .with_state(state.0.clone())
         .nest("/details", details::routes(state))
 }
@@ -0,0 +1,90 @@
+//! Polars queries for the search suggestions
+
+use super::{SearchReq, SearchRes, SearchSuggestion};
+use crate::api::v1::filter::columns;
+use crate::api::v1::filter::dataframe::{similarity_expr, text_expr};
+use crate::server::AppState;
+use crate::store::Table;
+use polars::prelude::*;
+
+/// Builds the suggestions of a kind from the frequency of a column
+fn suggestions(
+    details: LazyFrame,
+    kind: &str,
+    label: Expr,
+    value: Expr,
+    req: &SearchReq,
+) -> LazyFrame {
+    details
+        .group_by([value.alias("value")])
+        .agg([
+            label.first().alias("label"),
+            col(columns::REPLAY_ID).n_unique().alias("count"),
+        ])
+        .filter(text_expr("label", &req.q, req.mode))
+        .select([
+            lit(kind).alias("kind"),
+            col("label"),
+            col("value").cast(DataType::String),
+            col("count").cast(DataType::UInt32),
+            similarity_expr("label", &req.q).alias("score"),
+        ])
+}
+
+/// Gets the players, maps and replays matching the search, the best matches first
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn query_search(
+    req: SearchReq,
+    state: AppState,
+) -> Result<DataFrame, crate::error::Error> {
+    let details = state.store.scan(Table::Details)?.with_column(
+        col(columns::PLAYER_NAME)
+            .str()
+            .split(lit("<sp/>"))
+            .list()
+            .last()
+            .alias(columns::PLAYER_NAME),
+    );
+    let players = suggestions(
+        details.clone(),
+        "player",
+        col(columns::PLAYER_NAME),
+        col(columns::PLAYER_NAME),
+        &req,
+    );
+    let maps = suggestions(
+        details.clone(),
+        "map",
+        col(columns::TITLE),
+        col(columns::TITLE),
+        &req,
+    );
+    let replays = suggestions(
+        details,
+        "replay",
+        col(columns::FILE_NAME),
+        col(columns::REPLAY_ID),
+        &req,
+    );
+    let query = concat([players, maps, replays], UnionArgs::default())?
+        .sort_by_exprs(
+            [col("score"), col("count")],
+            SortMultipleOptions {
+                descending: vec![true, true],
+                ..Default::default()
+            },
+        )
+        .limit(req.limit);
+    state.queries.collect(query).await
+}
+
+/// Gets the search suggestions as typed records
+pub async fn get_search(req: SearchReq, state: AppState) -> Result<SearchRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_search(req, state).await?;
+    let data: Vec<SearchSuggestion> = crate::common::df_to_records(&res)?;
+    Ok(SearchRes {
+        meta: meta.with_total(data.len()).build(),
+        data,
+    })
+}
@@ -0,0 +1,204 @@
+//! Suggestions of players, maps and replays for the search box
+
+use crate::api::client::{ApiClient, ApiError};
+use crate::api::v1::filter::SearchMode;
+use crate::api::validation::{normalize_text, FieldErrors};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+pub mod ui;
+
+/// The default number of suggestions
+pub const DEFAULT_SEARCH_LIMIT: u32 = 10;
+/// The maximum number of suggestions
+pub const MAX_SEARCH_LIMIT: u32 = 50;
+
+/// A search for suggestions
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SearchReq {
+    /// The text to search
+    #[serde(default)]
+    pub q: String,
+    /// How the text is matched
+    #[serde(default)]
+    pub mode: SearchMode,
+    /// The maximum number of suggestions
+    #[serde(default = "SearchReq::default_limit")]
+    pub limit: u32,
+}
+
+impl Default for SearchReq {
+    fn default() -> Self {
+        Self {
+            q: String::new(),
+            mode: SearchMode::default(),
+            limit: Self::default_limit(),
+        }
+    }
+}
+
+impl SearchReq {
+    /// The default number of suggestions
+    pub fn default_limit() -> u32 {
+        DEFAULT_SEARCH_LIMIT
+    }
+
+    /// Normalizes the search and rejects invalid values.
+    pub fn validate(self) -> Result<Self, FieldErrors> {
+        let mut errors = FieldErrors::new();
+        let q = normalize_text("q", self.q, &mut errors);
+        self.mode.check("q", &q, &mut errors);
+        if self.limit == 0 || self.limit > MAX_SEARCH_LIMIT {
+            errors.add(
+                "limit",
+                format!("Must be between 1 and {}", MAX_SEARCH_LIMIT),
+            );
+        }
+        errors.into_result(Self { q, ..self })
+    }
+}
+
+/// The kind of a suggestion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    Player,
+    Map,
+    Replay,
+}
+
+impl SuggestionKind {
+    /// The label of the kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Player => "Player",
+            Self::Map => "Map",
+            Self::Replay => "Replay",
+        }
+    }
+}
+
+/// A suggestion for the search box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchSuggestion {
+    /// Whether it's a player, a map or a replay
+    pub kind: SuggestionKind,
+    /// The text shown to the user
+    pub label: String,
+    /// The value of the filter, the player name, the map title or the replay id
+    pub value: String,
+    /// The number of replays of the suggestion
+    pub count: u32,
+    /// The similarity with the search, higher is better
+    pub score: f64,
+}
+
+/// The suggestions for a search
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct SearchRes {
+    /// Metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The data of the response
+    pub data: Vec<SearchSuggestion>,
+}
+
+/// The search box of the top bar
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct SC2SearchBox {
+    /// The search, the mode is persisted
+    request: SearchReq,
+
+    /// The suggestions for the current search
+    #[serde(skip)]
+    suggestions: Option<poll_promise::Promise<Result<SearchRes, ApiError>>>,
+
+    /// The client to the backend API
+    #[serde(skip)]
+    pub client: ApiClient,
+}
+
+impl SC2SearchBox {
+    /// Requests the suggestions for the current search to the HTTP server.
+    pub fn req_search(&mut self) {
+        let request = match self.request.clone().validate() {
+            Ok(request) => request,
+            Err(e) => {
+                #[cfg(target_arch = "wasm32")]
+                log::warn!("Invalid search: {}", e);
+                #[cfg(not(target_arch = "wasm32"))]
+                tracing::warn!("Invalid search: {}", e);
+                self.suggestions = None;
+                return;
+            }
+        };
+        let client = self.client.clone();
+        let fut = async move { client.search(&request).await };
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.suggestions = Some(poll_promise::Promise::spawn_local(fut));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.suggestions = Some(poll_promise::Promise::spawn_async(fut));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_search_req() {
+        let req: SearchReq = serde_urlencoded::from_str("q=%20serral%20&mode=fuzzy").unwrap();
+        let req = req.validate().unwrap();
+        assert_eq!(req.q, "serral");
+        assert_eq!(req.mode, SearchMode::Fuzzy);
+        assert_eq!(req.limit, DEFAULT_SEARCH_LIMIT);
+        let errors = SearchReq {
+            q: "[".to_string(),
+            mode: SearchMode::Regex,
+            limit: 0,
+        }
+        .validate()
+        .unwrap_err();
+        assert!(errors.get("q").is_some());
+        assert!(errors.get("limit").is_some());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[tokio::test]
+    async fn test_get_search_memory_store() {
+        use polars::prelude::*;
+        let state = crate::store::test_support::details_state(
+            df!(
+                "title" => ["Alcyone LE", "Alcyone LE", "Site Delta LE", "Site Delta LE"],
+                "player_name" => ["Serral", "Clem", "Serral", "Solar"],
+                "ext_fs_id" => [1u64, 1, 2, 2],
+                "ext_fs_replay_file_name" => ["a.SC2Replay", "a.SC2Replay", "serral_solar.SC2Replay", "serral_solar.SC2Replay"],
+            )
+            .unwrap(),
+        );
+        let req = SearchReq {
+            q: "srl".to_string(),
+            mode: SearchMode::Fuzzy,
+            ..Default::default()
+        };
+        let res = super::dataframe::get_search(req, state).await.unwrap();
+        assert_eq!(res.data[0].kind, SuggestionKind::Player);
+        assert_eq!(res.data[0].value, "Serral");
+        assert_eq!(res.data[0].count, 2);
+        // Solar does not have the characters in order, the replay file name does.
+        assert!(res.data.iter().all(|s| s.label != "Solar"));
+        assert!(res
+            .data
+            .iter()
+            .any(|s| s.kind == SuggestionKind::Replay && s.value == "2"));
+    }
+}
@@ -0,0 +1,77 @@
+//! Contains the UI for the search box of the top bar.
+
+use super::{SC2SearchBox, SearchSuggestion};
+use crate::api::v1::filter::SearchMode;
+use eframe::egui;
+use egui::Ui;
+
+impl SC2SearchBox {
+    /// Builds the search box with the suggestions below it, returns the picked suggestion.
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<SearchSuggestion> {
+        let mut picked = None;
+        let popup_id = ui.make_persistent_id("search_suggestions");
+        let response = egui::TextEdit::singleline(&mut self.request.q)
+            .hint_text("Search players, maps, replays")
+            .desired_width(200.0)
+            .show(ui)
+            .response;
+        let mut changed = response.changed();
+        egui::ComboBox::from_id_salt("search_mode")
+            .selected_text(self.request.mode.name())
+            .width(80.0)
+            .show_ui(ui, |ui| {
+                for mode in SearchMode::all() {
+                    changed |= ui
+                        .selectable_value(&mut self.request.mode, *mode, mode.name())
+                        .changed();
+                }
+            });
+        if changed {
+            if self.request.q.trim().is_empty() {
+                self.suggestions = None;
+            } else {
+                self.req_search();
+                ui.memory_mut(|mem| mem.open_popup(popup_id));
+            }
+        }
+        if response.gained_focus() && self.suggestions.is_some() {
+            ui.memory_mut(|mem| mem.open_popup(popup_id));
+        }
+        egui::popup::popup_below_widget(
+            ui,
+            popup_id,
+            &response,
+            egui::PopupCloseBehavior::CloseOnClickOutside,
+            |ui| {
+                ui.set_min_width(280.0);
+                match self.suggestions.as_ref().map(|p| p.ready()) {
+                    Some(Some(Ok(res))) if res.data.is_empty() => {
+                        ui.label("No matches");
+                    }
+                    Some(Some(Ok(res))) => {
+                        for suggestion in &res.data {
+                            let text = format!(
+                                "{}: {} ({})",
+                                suggestion.kind.name(),
+                                suggestion.label,
+                                suggestion.count
+                            );
+                            if ui.selectable_label(false, text).clicked() {
+                                picked = Some(suggestion.clone());
+                            }
+                        }
+                    }
+                    Some(Some(Err(e))) => crate::app::show_api_error(ui, e),
+                    Some(None) => {
+                        ui.spinner();
+                    }
+                    None => {}
+                }
+            },
+        );
+        if picked.is_some() {
+            ui.memory_mut(|mem| mem.close_popup());
+        }
+        picked
+    }
+}
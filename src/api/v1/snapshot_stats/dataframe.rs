@@ -16,9 +16,8 @@ pub async fn get_metadata(state: AppState) -> Result<SnapshotStats, crate::error
         let size = metadata.len();
         directory_size += size;
     }
-    // get the date_modified of the details.ipc file
-    let details_ipc_filename = format!("{}/{}", state.source_dir, crate::DETAILS_IPC);
-    let date_modified = std::fs::metadata(details_ipc_filename)?.modified()?;
+    // get the date_modified of the details table
+    let date_modified = state.store.date_modified()?;
     Ok(SnapshotStats {
         directory_size,
         date_modified,
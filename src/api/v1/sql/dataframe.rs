@@ -0,0 +1,181 @@
+//! Polars SQL context over the snapshot tables
+
+use super::{SqlQueryReq, SqlQueryRes};
+use crate::api::v1::filter::columns;
+use crate::api::v1::filter::dataframe::with_team_layout;
+use crate::server::AppState;
+use crate::store::Table;
+use polars::prelude::*;
+use polars::sql::SQLContext;
+use sqlparser::ast::{Query, TableFactor, Visit, Visitor};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+
+/// Creates a SQL context with every snapshot table registered by its name, i.e. `details`,
+/// the team layout of each replay is available in the `team_layouts` table.
+pub fn sql_context(state: &AppState) -> SQLContext {
+    let mut ctx = SQLContext::new();
+    for table in Table::all() {
+        match state.store.scan(*table) {
+            Ok(lf) => ctx.register(table.file_stem(), lf),
+            Err(e) => tracing::debug!("Table {} not available for SQL: {}", table, e),
+        }
+    }
+    if let Ok(details) = state.store.scan(Table::Details) {
+        ctx.register(
+            "team_layouts",
+            with_team_layout(details)
+                .select([col(columns::REPLAY_ID), col(columns::TEAM_LAYOUT)])
+                .unique(None, UniqueKeepStrategy::Any),
+        );
+    }
+    ctx
+}
+
+/// Collects the common table expressions and rejects the sources that are not registered tables.
+struct TableSources<'a> {
+    /// The tables registered in the SQL context
+    tables: &'a HashSet<String>,
+    /// The names of the common table expressions seen so far
+    ctes: HashSet<String>,
+}
+
+impl Visitor for TableSources<'_> {
+    type Break = String;
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<Self::Break> {
+        if let Some(with) = &query.with {
+            for cte in &with.cte_tables {
+                self.ctes.insert(cte.alias.name.value.clone());
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<Self::Break> {
+        match table_factor {
+            TableFactor::Table { name, args, .. } => {
+                if args.is_some() {
+                    return ControlFlow::Break(format!(
+                        "Table functions are not allowed: {}",
+                        name
+                    ));
+                }
+                let name = name
+                    .0
+                    .iter()
+                    .map(|ident| ident.value.as_str())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                if !self.tables.contains(&name) && !self.ctes.contains(&name) {
+                    return ControlFlow::Break(format!("Unknown table: {}", name));
+                }
+            }
+            TableFactor::Function { name, .. } => {
+                return ControlFlow::Break(format!("Table functions are not allowed: {}", name));
+            }
+            TableFactor::TableFunction { expr, .. } => {
+                return ControlFlow::Break(format!("Table functions are not allowed: {}", expr));
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Checks that every `FROM` and `JOIN` source of the query is a table registered in the
+/// context or a common table expression, Polars table functions like `read_csv` would
+/// otherwise read arbitrary files on the host.
+pub fn check_table_sources(ctx: &SQLContext, query: &str) -> Result<(), crate::error::Error> {
+    let statements = Parser::parse_sql(&GenericDialect, query)
+        .map_err(|e| crate::error::Error::Unprocessable(e.to_string()))?;
+    let tables: HashSet<String> = ctx.get_tables().into_iter().collect();
+    let mut sources = TableSources {
+        tables: &tables,
+        ctes: HashSet::new(),
+    };
+    match statements.visit(&mut sources) {
+        ControlFlow::Break(reason) => Err(crate::error::Error::BadRequest(reason)),
+        ControlFlow::Continue(()) => Ok(()),
+    }
+}
+
+/// Runs a read-only SQL query over the snapshot tables
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn query_sql(
+    req: SqlQueryReq,
+    state: AppState,
+) -> Result<DataFrame, crate::error::Error> {
+    req.validate().map_err(crate::error::Error::BadRequest)?;
+    let mut ctx = sql_context(&state);
+    check_table_sources(&ctx, &req.query)?;
+    let query = ctx.execute(&req.query)?.limit(req.limit);
+    state.queries.collect(query).await
+}
+
+/// Runs a read-only SQL query and returns the rows as JSON objects
+pub async fn get_sql(
+    req: SqlQueryReq,
+    state: AppState,
+) -> Result<SqlQueryRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_sql(req, state).await?;
+    let columns = res
+        .get_column_names()
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect();
+    let data: Vec<serde_json::Map<String, serde_json::Value>> = crate::common::df_to_records(&res)?;
+    Ok(SqlQueryRes {
+        meta: meta.with_total(data.len()).build(),
+        columns,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_sql_rejects_table_functions() {
+        let state = crate::store::test_support::details_state(
+            df!(
+                "title" => ["Alcyone LE", "Oceanborn LE"],
+                "ext_fs_id" => [1u64, 2],
+            )
+            .unwrap(),
+        );
+        let req = |query: &str| SqlQueryReq {
+            query: query.to_string(),
+            ..Default::default()
+        };
+        let res = query_sql(req("SELECT title FROM details"), state.clone())
+            .await
+            .unwrap();
+        assert_eq!(res.height(), 2);
+        let res = query_sql(
+            req("WITH t AS (SELECT * FROM details) SELECT title FROM t"),
+            state.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.height(), 2);
+        for query in [
+            "SELECT * FROM read_csv('/etc/passwd')",
+            "SELECT * FROM details JOIN read_parquet('/tmp/x.parquet') USING (title)",
+            "SELECT * FROM details WHERE title IN (SELECT * FROM read_ipc('/tmp/x.ipc'))",
+            "SELECT * FROM users",
+        ] {
+            let err = query_sql(req(query), state.clone()).await.unwrap_err();
+            assert_eq!(
+                err.status_code(),
+                axum::http::StatusCode::BAD_REQUEST,
+                "{}",
+                query
+            );
+        }
+    }
+}
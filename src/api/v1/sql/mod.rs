@@ -0,0 +1,138 @@
+//! Ad-hoc read-only SQL queries over the snapshot tables
+
+pub mod ui;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dataframe;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+use crate::api::client::{ApiClient, ApiError};
+use serde::{Deserialize, Serialize};
+
+/// The default number of rows returned by a query
+pub const DEFAULT_SQL_LIMIT: u32 = 1000;
+
+/// The maximum number of rows a query may return
+pub const MAX_SQL_LIMIT: u32 = 10000;
+
+/// A SQL query over the snapshot tables
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SqlQueryReq {
+    /// The SELECT statement to run
+    #[serde(default)]
+    pub query: String,
+    /// The maximum number of rows to return
+    #[serde(default = "SqlQueryReq::default_limit")]
+    pub limit: u32,
+}
+
+impl Default for SqlQueryReq {
+    fn default() -> Self {
+        Self {
+            query:
+                "SELECT title, COUNT(*) AS count FROM details GROUP BY title ORDER BY count DESC"
+                    .to_string(),
+            limit: Self::default_limit(),
+        }
+    }
+}
+
+impl SqlQueryReq {
+    /// The default row limit of the queries
+    pub fn default_limit() -> u32 {
+        DEFAULT_SQL_LIMIT
+    }
+
+    /// Checks the query is a single read-only statement.
+    pub fn validate(&self) -> Result<(), String> {
+        let query = self.query.trim().trim_end_matches(';');
+        if query.is_empty() {
+            return Err("The query is empty".to_string());
+        }
+        if query.contains(';') {
+            return Err("Only one statement is allowed".to_string());
+        }
+        let first_keyword = query
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if !["select", "with", "show"].contains(&first_keyword.as_str()) {
+            return Err(format!(
+                "Only SELECT, WITH and SHOW statements are allowed, got: {}",
+                first_keyword
+            ));
+        }
+        if self.limit == 0 || self.limit > MAX_SQL_LIMIT {
+            return Err(format!("The limit must be between 1 and {}", MAX_SQL_LIMIT));
+        }
+        Ok(())
+    }
+}
+
+/// The result of a SQL query
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct SqlQueryRes {
+    /// Metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The names of the columns, in order
+    pub columns: Vec<String>,
+    /// The rows of the result, one JSON object per row
+    pub data: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct SC2SqlConsole {
+    /// The query to run
+    pub request: SqlQueryReq,
+
+    /// The result of the last query
+    #[serde(skip)]
+    result: Option<poll_promise::Promise<Result<SqlQueryRes, ApiError>>>,
+
+    /// The client to the backend API
+    #[serde(skip)]
+    pub client: ApiClient,
+}
+
+impl SC2SqlConsole {
+    /// Requests the async operation to run the SQL query on the HTTP server.
+    pub fn req_sql_query(&mut self) {
+        let client = self.client.clone();
+        let request = self.request.clone();
+        let fut = async move { client.sql(&request).await };
+        #[cfg(target_arch = "wasm32")]
+        {
+            log::info!("Requesting SQL query");
+            self.result = Some(poll_promise::Promise::spawn_local(fut));
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            tracing::info!("Requesting SQL query");
+            self.result = Some(poll_promise::Promise::spawn_async(fut));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_sql_query() {
+        let mut req = SqlQueryReq::default();
+        assert!(req.validate().is_ok());
+        req.query = "  with t as (select * from details) select * from t;".to_string();
+        assert!(req.validate().is_ok());
+        req.query = "DROP TABLE details".to_string();
+        assert!(req.validate().is_err());
+        req.query = "SELECT 1; DROP TABLE details".to_string();
+        assert!(req.validate().is_err());
+        req.query = "SELECT 1".to_string();
+        req.limit = MAX_SQL_LIMIT + 1;
+        assert!(req.validate().is_err());
+    }
+}
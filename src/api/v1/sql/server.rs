@@ -0,0 +1,36 @@
+//! Axum route handlers
+
+use super::SqlQueryReq;
+use crate::common::{DataFrameResponse, ResponseFormat};
+use crate::meta::ResponseMetaBuilder;
+use crate::server::AppState;
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Runs a read-only SQL query over the snapshot tables
+pub async fn route_query_sql(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    req: Result<Json<SqlQueryReq>, JsonRejection>,
+) -> Result<Response, crate::error::Error> {
+    if !state.features.sql {
+        return Err(crate::error::Error::NotFound(
+            "The SQL endpoint is disabled".to_string(),
+        ));
+    }
+    let Json(req) = req?;
+    tracing::info!("Querying SQL: {:?}", req);
+    let meta = ResponseMetaBuilder::new();
+    let format = ResponseFormat::from_headers(&headers);
+    if format == ResponseFormat::Json {
+        let res = super::dataframe::get_sql(req, state).await?;
+        Ok(Json(res).into_response())
+    } else {
+        let res = super::dataframe::query_sql(req, state).await?;
+        Ok(DataFrameResponse::new(meta, res, format).into_response())
+    }
+}
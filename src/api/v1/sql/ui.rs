@@ -0,0 +1,105 @@
+//! Contains the UI for the SQL query console.
+
+use crate::app::AppEvent;
+
+use super::{SC2SqlConsole, SqlQueryRes, MAX_SQL_LIMIT};
+use eframe::egui;
+use egui::Ui;
+use egui_extras::{Column, TableBuilder};
+
+impl SC2SqlConsole {
+    /// Builds a table for egui with the columns of the query result.
+    fn table_inner(&mut self, ui: &mut Ui, res: &SqlQueryRes) {
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto());
+        for _ in &res.columns {
+            table = table.column(Column::initial(100.0).at_least(40.0).clip(true));
+        }
+        table
+            .min_scrolled_height(0.0)
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Row");
+                });
+                for column in &res.columns {
+                    header.col(|ui| {
+                        ui.strong(column);
+                    });
+                }
+            })
+            .body(|body| {
+                let row_height = 18.0;
+                body.rows(row_height, res.data.len(), |mut row| {
+                    let idx = row.index();
+                    row.col(|ui| {
+                        ui.label(idx.to_string());
+                    });
+                    for column in &res.columns {
+                        row.col(|ui| {
+                            let value = match res.data[idx].get(column) {
+                                Some(serde_json::Value::String(value)) => value.clone(),
+                                Some(serde_json::Value::Null) | None => String::new(),
+                                Some(value) => value.to_string(),
+                            };
+                            ui.label(value);
+                        });
+                    }
+                });
+            });
+    }
+
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        is_open: &mut bool,
+        _tx: tokio::sync::mpsc::Sender<AppEvent>,
+    ) {
+        egui::Window::new("SQL Console")
+            .default_width(640.0)
+            .default_height(480.0)
+            .open(is_open)
+            .show(ctx, |ui| {
+                ui.label("Tables: details, init_data, unit_born, unit_died, stats, upgrades");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.request.query)
+                        .code_editor()
+                        .desired_rows(4)
+                        .desired_width(f32::INFINITY),
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Row limit: ");
+                    ui.add(egui::DragValue::new(&mut self.request.limit).range(1..=MAX_SQL_LIMIT));
+                    if ui.button("Run").clicked() {
+                        self.req_sql_query();
+                    }
+                });
+                ui.separator();
+                let res = match &self.result {
+                    Some(result) => match result.ready() {
+                        Some(Ok(res)) => res.clone(),
+                        Some(Err(e)) => {
+                            crate::app::show_api_error(ui, e);
+                            return;
+                        }
+                        None => {
+                            ui.spinner();
+                            return;
+                        }
+                    },
+                    None => return,
+                };
+                ui.label(format!(
+                    "{} rows in {} ms",
+                    res.meta.total, res.meta.duration
+                ));
+                ui.push_id("sql_result", |ui| {
+                    egui::ScrollArea::horizontal().show(ui, |ui| {
+                        self.table_inner(ui, &res);
+                    });
+                });
+            });
+    }
+}
@@ -1,38 +1,33 @@
 //! Polars queries for the unit born position Events
 
 use super::{UnitBornPosEvent, UnitBornPosReq, UnitBornPosRes};
+use crate::api::v1::filter::dataframe::scan_details_with_slots;
 use crate::server::AppState;
+use crate::store::Table;
 use polars::prelude::*;
 
 /// Gets the list of maps from the details.ipc file
 #[tracing::instrument(level = "debug", skip(state))]
-pub async fn get_map_freq(
+pub async fn query_unit_born_pos(
     req: UnitBornPosReq,
     state: AppState,
-) -> Result<UnitBornPosRes, crate::error::Error> {
-    let meta = crate::meta::ResponseMetaBuilder::new();
-    let mut query = LazyFrame::scan_ipc(
-        format!("{}/{}", state.source_dir, crate::UNIT_BORN_IPC),
-        Default::default(),
-    )?;
-    if let Some(replay_id) = req.replay_id {
-        query = query.filter(col("ext_fs_id").eq(lit(replay_id)));
-    }
-    if let Some(player_name) = req.player {
+) -> Result<DataFrame, crate::error::Error> {
+    let replay_ids = req
+        .filter
+        .replay_ids(scan_details_with_slots(&*state.store)?);
+    let mut query = state.store.scan(Table::UnitBorn)?.join(
+        replay_ids,
+        [col("ext_fs_id")],
+        [col("ext_fs_id")],
+        JoinArgs::new(JoinType::Semi),
+    );
+    if !req.filter.player.is_empty() {
         query = query.filter(
             col("ext_replay_detail_player_name")
                 .str()
                 .to_lowercase()
                 .str()
-                .contains_literal(lit(player_name.to_lowercase())),
-        );
-    } else {
-        // This would be for a neutral unit.
-        query = query.filter(
-            col("ext_replay_detail_player_name")
-                .str()
-                .to_lowercase()
-                .eq(lit("")),
+                .contains_literal(lit(req.filter.player.to_lowercase())),
         );
     }
     if !req.unit_type_name.is_empty() {
@@ -47,33 +42,36 @@ pub async fn get_map_freq(
     if let Some(game_loop) = req.game_loop {
         query = query.filter(col("ext_replay_loop").eq(lit(game_loop)));
     }
-    let res = tokio::task::spawn_blocking(|| {
-        query
-            .select(&[
-                col("unit_type_name"),
-                col("x"),
-                col("y"),
-                col("ext_replay_loop"),
-            ])
-            .sort(
-                ["ext_replay_loop"],
-                SortMultipleOptions {
-                    descending: vec![true],
-                    ..Default::default()
-                },
-            )
-            .limit(1000)
-            .collect()
-    })
-    .await
-    .unwrap();
-    let res = res?;
-    let data_str = crate::common::convert_df_to_json_data(&res)?;
-    tracing::info!("Data: {}", data_str);
-    let data: Vec<UnitBornPosEvent> = serde_json::from_str(&data_str)?;
+    let query = query
+        .select(&[
+            col("unit_type_name"),
+            col("x"),
+            col("y"),
+            col("ext_replay_loop"),
+        ])
+        .sort(
+            ["ext_replay_loop"],
+            SortMultipleOptions {
+                descending: vec![true],
+                ..Default::default()
+            },
+        )
+        .limit(1000);
+    state.queries.collect(query).await
+}
+
+/// Gets the unit born positions as typed records
+pub async fn get_map_freq(
+    req: UnitBornPosReq,
+    state: AppState,
+) -> Result<UnitBornPosRes, crate::error::Error> {
+    let meta = crate::meta::ResponseMetaBuilder::new();
+    let res = query_unit_born_pos(req, state).await?;
+    tracing::trace!("UnitBornPosRes: {:?}", res);
+    let data: Vec<UnitBornPosEvent> = crate::common::df_to_records(&res)?;
 
     Ok(UnitBornPosRes {
-        meta: meta.build(),
+        meta: meta.with_total(data.len()).build(),
         data,
     })
 }
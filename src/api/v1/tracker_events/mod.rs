@@ -13,14 +13,15 @@ use serde::{Deserialize, Serialize};
 /// A query for the Position of the Unit Born TrackerEvents
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct UnitBornPosReq {
-    /// The name of the player
-    pub player: Option<String>,
     /// The name of the unit
+    #[serde(default)]
     pub unit_type_name: String,
     /// An optional game loop of the event
     pub game_loop: Option<i64>,
-    /// The file sha256 hash of the replay
-    pub replay_id: Option<u64>,
+    /// The filters of the replays the events belong to, the same query parameters as the
+    /// details endpoints. The `player` also selects the units of the player.
+    #[serde(flatten)]
+    pub filter: crate::api::v1::filter::ReplayFilter,
 }
 
 /// Basic query response available for filtering replay maps
@@ -44,3 +45,25 @@ pub struct UnitBornPosEvent {
     /// The game loop of the event
     pub game_loop: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::v1::filter::TeamLayout;
+
+    #[test]
+    fn test_unit_born_pos_req_query_string() {
+        let req: UnitBornPosReq = serde_urlencoded::from_str(
+            "unit_type_name=Drone&game_loop=42&player=serral&layout=1v1&min_duration=60&include_observers=true",
+        )
+        .unwrap();
+        assert_eq!(req.unit_type_name, "Drone");
+        assert_eq!(req.game_loop, Some(42));
+        assert_eq!(req.filter.player, "serral");
+        assert_eq!(req.filter.layout, Some(TeamLayout::OneVsOne));
+        assert_eq!(req.filter.min_duration, Some(60));
+        assert!(req.filter.include_observers);
+        let query = serde_urlencoded::to_string(&req).unwrap();
+        assert!(query.contains("player=serral"));
+    }
+}
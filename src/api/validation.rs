@@ -0,0 +1,125 @@
+//! Validation of the request filters, shared by the server and the frontends so that the
+//! same messages can be shown inline next to the offending filter.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The maximum length of the free text filters
+pub const MAX_TEXT_LEN: usize = 128;
+
+/// The validation messages by the name of the offending field.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldErrors(pub BTreeMap<String, String>);
+
+impl FieldErrors {
+    /// Creates an empty set of errors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an error message for a field, the first error of a field is kept.
+    pub fn add(&mut self, field: &str, message: impl ToString) {
+        self.0
+            .entry(field.to_string())
+            .or_insert_with(|| message.to_string());
+    }
+
+    /// Returns the error message of a field, if any.
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0.get(field).map(String::as_str)
+    }
+
+    /// Whether there are no errors
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns Ok if there are no errors.
+    pub fn into_result<T>(self, value: T) -> Result<T, FieldErrors> {
+        if self.is_empty() {
+            Ok(value)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl std::fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self
+            .0
+            .iter()
+            .map(|(field, message)| format!("{}: {}", field, message))
+            .collect();
+        write!(f, "{}", messages.join(", "))
+    }
+}
+
+impl std::error::Error for FieldErrors {}
+
+/// Trims a free text filter and checks its length.
+pub fn normalize_text(field: &str, value: String, errors: &mut FieldErrors) -> String {
+    let value = value.trim().to_string();
+    if value.chars().count() > MAX_TEXT_LEN {
+        errors.add(
+            field,
+            format!("Must be at most {} characters long", MAX_TEXT_LEN),
+        );
+    }
+    value
+}
+
+/// Trims a replay id filter and checks it is a snapshot replay id, which is numeric.
+pub fn normalize_replay_id(field: &str, value: String, errors: &mut FieldErrors) -> String {
+    let value = value.trim().to_string();
+    if !value.is_empty() && value.parse::<u64>().is_err() {
+        errors.add(field, "Must be a numeric snapshot replay id");
+    }
+    value
+}
+
+/// Checks the date range is not inverted.
+pub fn check_date_range(
+    min_field: &str,
+    min_date: chrono::NaiveDate,
+    max_field: &str,
+    max_date: chrono::NaiveDate,
+    errors: &mut FieldErrors,
+) {
+    if min_date > max_date {
+        errors.add(min_field, format!("Must be before the {}", max_field));
+        errors.add(max_field, format!("Must be after the {}", min_field));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_filters() {
+        let mut errors = FieldErrors::new();
+        assert_eq!(
+            normalize_text("title", " Alcyone ".to_string(), &mut errors),
+            "Alcyone"
+        );
+        assert_eq!(
+            normalize_replay_id("replay_id", " 42 ".to_string(), &mut errors),
+            "42"
+        );
+        assert!(errors.is_empty());
+        normalize_replay_id("replay_id", "abc".to_string(), &mut errors);
+        normalize_text("title", "x".repeat(MAX_TEXT_LEN + 1), &mut errors);
+        check_date_range(
+            "file_min_date",
+            chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            "file_max_date",
+            chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            &mut errors,
+        );
+        assert!(errors.get("replay_id").is_some());
+        assert!(errors.get("title").is_some());
+        assert!(errors.get("file_min_date").is_some());
+        assert!(errors.get("file_max_date").is_some());
+    }
+}
@@ -1,9 +1,15 @@
 //! Main app
 
+use crate::api::client::{ApiClient, ApiError};
 use crate::api::v1::details::maps::SC2MapPicker;
 use crate::api::v1::details::players::SC2PlayerPicker;
+use crate::api::v1::details::teams::SC2TeamPicker;
+use crate::api::v1::details::vs_ai::SC2VsAiStats;
+use crate::api::v1::search::{SC2SearchBox, SearchSuggestion, SuggestionKind};
 use crate::api::v1::snapshot_stats::SnapshotStats;
+use crate::api::v1::sql::SC2SqlConsole;
 use crate::api::v1::tracker_events::UnitBornPosRes;
+use crate::api::validation::FieldErrors;
 use chrono::prelude::*;
 use eframe::egui;
 
@@ -13,7 +19,11 @@ use eframe::egui;
 pub struct SC2ReplayExplorer {
     /// Contains the metadata related to the backend snapshot.
     #[serde(skip)]
-    snapshot_stats: Option<poll_promise::Promise<SnapshotStats>>,
+    snapshot_stats: Option<poll_promise::Promise<Result<SnapshotStats, ApiError>>>,
+
+    /// The client to the backend API
+    #[serde(skip)]
+    client: ApiClient,
 
     /// The Map selection UI
     #[serde(skip)]
@@ -23,6 +33,20 @@ pub struct SC2ReplayExplorer {
     #[serde(skip)]
     player_picker: SC2PlayerPicker,
 
+    /// The Team selection UI
+    #[serde(skip)]
+    team_picker: SC2TeamPicker,
+
+    /// The games against the A.I. UI
+    #[serde(skip)]
+    vs_ai_stats: SC2VsAiStats,
+
+    /// The search box of the top bar, the search mode is persisted
+    search_box: SC2SearchBox,
+
+    /// The SQL query console, the last query is persisted
+    sql_console: SC2SqlConsole,
+
     /// The Map selection UI
     #[serde(skip)]
     units_born: UnitBornPosRes,
@@ -56,6 +80,18 @@ pub struct SC2ReplayExplorer {
     #[serde(skip)]
     pub is_open_player_selection: bool,
 
+    /// Wether the team selection is open
+    #[serde(skip)]
+    pub is_open_team_selection: bool,
+
+    /// Wether the vs A.I. statistics are open
+    #[serde(skip)]
+    pub is_open_vs_ai_stats: bool,
+
+    /// Wether the SQL console is open
+    #[serde(skip)]
+    pub is_open_sql_console: bool,
+
     /// A control channel handle for the different elements
     #[serde(skip)]
     tx: tokio::sync::mpsc::Sender<AppEvent>,
@@ -79,8 +115,13 @@ impl Default for SC2ReplayExplorer {
         let (tx, rx) = tokio::sync::mpsc::channel(100);
         Self {
             snapshot_stats: None,
+            client: Default::default(),
             map_picker: Default::default(),
             player_picker: Default::default(),
+            team_picker: Default::default(),
+            vs_ai_stats: Default::default(),
+            search_box: Default::default(),
+            sql_console: Default::default(),
             units_born: Default::default(),
             value: 2.7,
             dropped_files: Default::default(),
@@ -90,6 +131,9 @@ impl Default for SC2ReplayExplorer {
             replay_details_status_color: egui::Color32::GREEN,
             is_open_map_selection: false,
             is_open_player_selection: false,
+            is_open_team_selection: false,
+            is_open_vs_ai_stats: false,
+            is_open_sql_console: false,
             tx,
             rx,
         }
@@ -98,7 +142,14 @@ impl Default for SC2ReplayExplorer {
 
 impl SC2ReplayExplorer {
     /// Called once before the first frame.
+    /// The requests are relative to the page serving the app.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self::with_api_client(cc, ApiClient::default())
+    }
+
+    /// Called once before the first frame, the requests are sent through the client.
+    /// The native app needs it as relative URLs have no host outside the browser.
+    pub fn with_api_client(cc: &eframe::CreationContext<'_>, client: ApiClient) -> Self {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         let mut app_state: SC2ReplayExplorer = if let Some(storage) = cc.storage {
@@ -107,26 +158,29 @@ impl SC2ReplayExplorer {
         } else {
             Default::default()
         };
+        app_state.set_api_client(client);
         app_state.map_picker.req_details_maps();
         app_state.player_picker.req_details_players();
+        app_state.team_picker.req_details_teams();
+        app_state.vs_ai_stats.req_details_vs_ai();
         app_state.req_snapshot_stats();
         app_state
     }
 
+    /// Sets the client used by the app and its windows to reach the backend
+    pub fn set_api_client(&mut self, client: ApiClient) {
+        self.map_picker.client = client.clone();
+        self.player_picker.client = client.clone();
+        self.team_picker.client = client.clone();
+        self.vs_ai_stats.client = client.clone();
+        self.sql_console.client = client.clone();
+        self.search_box.client = client.clone();
+        self.client = client;
+    }
+
     /// Spawns the Majordomo Coordinator
     pub fn spawn_mdp_coordinator(&mut self) {
-        #[cfg(target_arch = "wasm32")]
-        {
-            self.snapshot_stats = Some(poll_promise::Promise::spawn_local(
-                Self::get_snapshot_stats(),
-            ));
-        }
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.snapshot_stats = Some(poll_promise::Promise::spawn_async(
-                Self::get_snapshot_stats(),
-            ));
-        }
+        self.req_snapshot_stats();
     }
 
     /// Starts the Majordomo Coordinator , which is a loop that handles the different
@@ -159,28 +213,41 @@ impl SC2ReplayExplorer {
         }
     }
 
-    /// Loads basic information about the analyzed metadata
-    async fn get_snapshot_stats() -> SnapshotStats {
-        let request = ehttp::Request::get("/api/v1/snapshot_stats");
-        ehttp::fetch_async(request)
-            .await
-            .map(|response| serde_json::from_slice(&response.bytes).unwrap_or_default())
-            .unwrap_or_default()
+    /// Applies a suggestion of the search box to the matching picker and opens it
+    fn apply_search_suggestion(&mut self, suggestion: SearchSuggestion) {
+        match suggestion.kind {
+            SuggestionKind::Player => {
+                self.player_picker
+                    .apply_filter(|filter| filter.player = suggestion.value);
+                self.player_picker.selected_player = None;
+                self.is_open_player_selection = true;
+            }
+            SuggestionKind::Map => {
+                self.map_picker
+                    .apply_filter(|filter| filter.title = suggestion.value);
+                self.map_picker.selected_map = None;
+                self.is_open_map_selection = true;
+            }
+            SuggestionKind::Replay => {
+                self.map_picker
+                    .apply_filter(|filter| filter.replay_id = suggestion.value);
+                self.map_picker.selected_map = None;
+                self.is_open_map_selection = true;
+            }
+        }
     }
 
     /// Spawns the async operation to get the snapshot stats from the backend
     fn req_snapshot_stats(&mut self) {
+        let client = self.client.clone();
+        let fut = async move { client.snapshot_stats().await };
         #[cfg(target_arch = "wasm32")]
         {
-            self.snapshot_stats = Some(poll_promise::Promise::spawn_local(
-                Self::get_snapshot_stats(),
-            ));
+            self.snapshot_stats = Some(poll_promise::Promise::spawn_local(fut));
         }
         #[cfg(not(target_arch = "wasm32"))]
         {
-            self.snapshot_stats = Some(poll_promise::Promise::spawn_async(
-                Self::get_snapshot_stats(),
-            ));
+            self.snapshot_stats = Some(poll_promise::Promise::spawn_async(fut));
         }
     }
 }
@@ -232,11 +299,25 @@ impl eframe::App for SC2ReplayExplorer {
                 ui.add_space(16.0);
 
                 ui.horizontal(|ui| {
+                    if let Some(suggestion) = self.search_box.ui(ui) {
+                        self.apply_search_suggestion(suggestion);
+                    }
                     if ui.button("Reload Stats").clicked() {
                         self.req_snapshot_stats();
                     }
+                    if ui.button("Teams").clicked() {
+                        self.is_open_team_selection = true;
+                    }
+                    if ui.button("vs A.I.").clicked() {
+                        self.is_open_vs_ai_stats = true;
+                    }
+                    if ui.button("SQL Console").clicked() {
+                        self.is_open_sql_console = true;
+                    }
                     if let Some(snapshot_stats) = &self.snapshot_stats {
-                        if let Some(snapshot_stats) = snapshot_stats.ready() {
+                        if let Some(Err(e)) = snapshot_stats.ready() {
+                            show_api_error(ui, e);
+                        } else if let Some(Ok(snapshot_stats)) = snapshot_stats.ready() {
                             // Crete a floating panel with the stats
                             /*ui.label(format!("Total files: {}", snapshot_stats.num_files));
                             ui.label(format!("Total maps: {}", snapshot_stats.num_maps));
@@ -312,6 +393,18 @@ impl eframe::App for SC2ReplayExplorer {
                     .update(ctx, &mut is_open_player_selection, self.tx.clone());
                 self.is_open_player_selection = self.player_picker.selected_player.is_none();
             });
+            let mut is_open_team_selection = self.is_open_team_selection;
+            self.team_picker
+                .update(ctx, &mut is_open_team_selection, self.tx.clone());
+            self.is_open_team_selection = is_open_team_selection;
+            let mut is_open_vs_ai_stats = self.is_open_vs_ai_stats;
+            self.vs_ai_stats
+                .update(ctx, &mut is_open_vs_ai_stats, self.tx.clone());
+            self.is_open_vs_ai_stats = is_open_vs_ai_stats;
+            let mut is_open_sql_console = self.is_open_sql_console;
+            self.sql_console
+                .update(ctx, &mut is_open_sql_console, self.tx.clone());
+            self.is_open_sql_console = is_open_sql_console;
             if let Some(file_async) = &self.file_request_future {
                 if let Some(Some(file_contents)) = file_async.ready() {
                     self.replay_details = match s2protocol::parser::parse(file_contents) {
@@ -365,6 +458,37 @@ impl eframe::App for SC2ReplayExplorer {
     }
 }
 
+/// Shows an API error in a red frame, with a hint of what to check when available.
+pub fn show_api_error(ui: &mut egui::Ui, error: &ApiError) {
+    egui::Frame::group(ui.style())
+        .stroke(egui::Stroke::new(1.0, egui::Color32::RED))
+        .show(ui, |ui| {
+            ui.colored_label(egui::Color32::RED, error.to_string());
+            if let Some(hint) = error.hint() {
+                ui.label(hint);
+            }
+        });
+}
+
+/// Shows the heading of a table, with a spinner while its query is pending.
+pub fn show_table_heading(ui: &mut egui::Ui, heading: &str, is_pending: bool) {
+    ui.horizontal(|ui| {
+        ui.heading(heading);
+        if is_pending {
+            ui.spinner();
+        }
+    });
+}
+
+/// Shows the validation message of a field next to its input, if any.
+pub fn show_field_error(ui: &mut egui::Ui, errors: &FieldErrors, field: &str) {
+    if let Some(message) = errors.get(field) {
+        ui.colored_label(egui::Color32::RED, "⚠")
+            .on_hover_text(message);
+        ui.small(message);
+    }
+}
+
 fn references_footer(ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
         ui.spacing_mut().item_spacing.x = 0.0;
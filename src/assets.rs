@@ -0,0 +1,132 @@
+//! The frontend files served by the web server
+//!
+//! The files built by `trunk build` are served from a directory, or from the binary itself
+//! when it is built with the `embed-assets` feature. The paths without an extension that do
+//! not match a file are answered with `index.html`, so that the deep links into the app load.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use tower::util::BoxCloneService;
+use tower::ServiceExt;
+use tower_http::services::{ServeDir, ServeFile};
+
+/// The default directory of the frontend files, relative to the working directory
+pub const DEFAULT_STATIC_DIR: &str = "dist";
+
+/// Where the frontend files are read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticAssets {
+    /// A directory, i.e. the output of `trunk build`
+    Dir(PathBuf),
+    /// The files embedded in the binary at build time
+    #[cfg(feature = "embed-assets")]
+    Embedded,
+}
+
+impl StaticAssets {
+    /// The configured directory, otherwise the embedded files when built with them, otherwise
+    /// `dist`.
+    pub fn new(static_dir: Option<&str>) -> Self {
+        match static_dir {
+            Some(dir) => Self::Dir(PathBuf::from(dir)),
+            #[cfg(feature = "embed-assets")]
+            None => Self::Embedded,
+            #[cfg(not(feature = "embed-assets"))]
+            None => Self::Dir(PathBuf::from(DEFAULT_STATIC_DIR)),
+        }
+    }
+
+    /// The service answering the requests that match no route
+    pub fn service(&self) -> BoxCloneService<Request, Response, Infallible> {
+        match self {
+            Self::Dir(dir) => {
+                if !dir.join("index.html").is_file() {
+                    tracing::warn!(
+                        "No index.html in {}, run `trunk build` or set --static-dir",
+                        dir.display()
+                    );
+                }
+                let index = dir.join("index.html");
+                let fallback = tower::service_fn(move |req: Request| {
+                    let index = index.clone();
+                    async move {
+                        if !is_app_route(req.uri().path()) {
+                            return Ok::<_, Infallible>(StatusCode::NOT_FOUND.into_response());
+                        }
+                        let res = ServeFile::new(index).oneshot(req).await?;
+                        Ok(res.map(Body::new))
+                    }
+                });
+                ServeDir::new(dir)
+                    .fallback(fallback)
+                    .map_response(|res| res.map(Body::new))
+                    .boxed_clone()
+            }
+            #[cfg(feature = "embed-assets")]
+            Self::Embedded => tower::service_fn(|req: Request| async move {
+                Ok::<_, Infallible>(embedded::serve(req.uri().path()))
+            })
+            .boxed_clone(),
+        }
+    }
+}
+
+/// Whether the path is a route of the app rather than a file, i.e. `/replays/42` but not
+/// `/eframe_sc2.js`.
+pub fn is_app_route(path: &str) -> bool {
+    Path::new(path).extension().is_none()
+}
+
+/// The frontend files built into the binary
+#[cfg(feature = "embed-assets")]
+mod embedded {
+    use axum::{
+        http::{header, StatusCode},
+        response::{IntoResponse, Response},
+    };
+
+    /// The output of `trunk build`, it must exist when the binary is built.
+    #[derive(rust_embed::RustEmbed)]
+    #[folder = "dist/"]
+    struct Dist;
+
+    /// Serves an embedded file, `index.html` for the routes of the app.
+    pub fn serve(path: &str) -> Response {
+        let path = match path.trim_start_matches('/') {
+            "" => "index.html",
+            path => path,
+        };
+        let file = match Dist::get(path) {
+            Some(file) => file,
+            None if super::is_app_route(path) => match Dist::get("index.html") {
+                Some(file) => file,
+                None => return StatusCode::NOT_FOUND.into_response(),
+            },
+            None => return StatusCode::NOT_FOUND.into_response(),
+        };
+        (
+            [(header::CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data.into_owned(),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_app_route() {
+        assert!(is_app_route("/"));
+        assert!(is_app_route("/replays/42"));
+        assert!(!is_app_route("/eframe_sc2_bg.wasm"));
+        assert!(!is_app_route("/assets/favicon.ico"));
+    }
+}
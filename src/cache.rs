@@ -0,0 +1,386 @@
+//! Server side cache of the API responses
+//!
+//! The UI re-issues the same queries on start-up and while typing in the filters. The
+//! successful GET responses are kept in memory, keyed by the normalized request and the
+//! version of the snapshot, and are tagged with an `ETag` so that the clients can skip
+//! downloading results they already have.
+
+use crate::server::AppState;
+use crate::store::Table;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// The limits of the response cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// The maximum number of responses kept, 0 disables the cache
+    pub max_entries: usize,
+    /// The maximum size of the kept response bodies in bytes
+    pub max_bytes: usize,
+    /// How long a response is kept
+    pub ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 256,
+            max_bytes: 64 * 1024 * 1024,
+            ttl: Duration::from_secs(300),
+        }
+    }
+}
+
+/// A successful response and its ETag
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    /// The strong ETag of the body, quoted
+    pub etag: String,
+    /// The headers of the original response, i.e. the Content-Type and the `x-meta-*`
+    pub headers: HeaderMap,
+    /// The serialized body
+    pub body: Bytes,
+    /// When the response was computed
+    pub created_at: Instant,
+}
+
+impl CachedResponse {
+    /// Tags the response by its data, so that a recomputed result that did not change keeps
+    /// its ETag.
+    pub fn new(headers: HeaderMap, body: Bytes) -> Self {
+        Self {
+            etag: data_etag(&headers, &body),
+            headers,
+            body,
+            created_at: Instant::now(),
+        }
+    }
+
+    /// Builds the response, `304 Not Modified` without body when the client already has it.
+    pub fn to_response(&self, if_none_match: Option<&str>, cache_status: &'static str) -> Response {
+        let mut response = if if_none_match.is_some_and(|tags| etag_matches(tags, &self.etag)) {
+            StatusCode::NOT_MODIFIED.into_response()
+        } else {
+            let mut response = Body::from(self.body.clone()).into_response();
+            *response.headers_mut() = self.headers.clone();
+            response
+        };
+        let headers = response.headers_mut();
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        // The browsers must revalidate, the results change with the snapshot.
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        headers.insert("x-cache", HeaderValue::from_static(cache_status));
+        response
+    }
+}
+
+/// The strong ETag of the data of a response. The `meta` of the JSON bodies is left out,
+/// it changes on every run, i.e. the query duration. The other formats carry the meta in
+/// the headers.
+pub fn data_etag(headers: &HeaderMap, body: &Bytes) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|mime| mime.starts_with(crate::common::ResponseFormat::Json.mime()));
+    let json = is_json
+        .then(|| serde_json::from_slice::<serde_json::Value>(body).ok())
+        .flatten();
+    match json {
+        Some(serde_json::Value::Object(mut object)) => {
+            object.remove("meta");
+            serde_json::Value::Object(object)
+                .to_string()
+                .hash(&mut hasher);
+        }
+        _ => body.hash(&mut hasher),
+    }
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Whether an `If-None-Match` header value matches the ETag, weak tags are compared by value.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|tag| {
+        let tag = tag.strip_prefix("W/").unwrap_or(tag);
+        tag == "*" || tag == etag
+    })
+}
+
+/// Sorts the query parameters and drops the empty ones, so that the same filters in another
+/// order share the cache entry.
+pub fn normalize_query(query: &str) -> String {
+    let mut pairs: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap_or_default();
+    pairs.retain(|(_, value)| !value.is_empty());
+    pairs.sort();
+    serde_urlencoded::to_string(pairs).unwrap_or_default()
+}
+
+/// The version of the snapshot, the modification times of all its tables, so that any
+/// regenerated table changes it. The tables that can not be read are `none`.
+pub fn snapshot_version(state: &AppState) -> String {
+    Table::all()
+        .iter()
+        .map(|table| {
+            state
+                .store
+                .table_modified(*table)
+                .ok()
+                .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or_else(|| "none".to_string(), |age| age.as_nanos().to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[derive(Debug, Default)]
+struct CacheEntries {
+    /// The responses and their insertion order
+    responses: HashMap<String, (u64, CachedResponse)>,
+    bytes: usize,
+    next_order: u64,
+}
+
+/// An in-memory cache of responses bounded by number of entries, size and age.
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    /// The limits of the cache
+    pub config: CacheConfig,
+    entries: Mutex<CacheEntries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResponseCache {
+    /// Creates a new empty ResponseCache
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: Default::default(),
+            hits: Default::default(),
+            misses: Default::default(),
+        }
+    }
+
+    /// Gets a response that has not expired
+    pub fn get(&self, key: &str) -> Option<CachedResponse> {
+        let cached = self.lookup(key);
+        let counter = if cached.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        cached
+    }
+
+    fn lookup(&self, key: &str) -> Option<CachedResponse> {
+        let mut entries = self.entries.lock().unwrap();
+        let (_, cached) = entries.responses.get(key)?;
+        if cached.created_at.elapsed() < self.config.ttl {
+            return Some(cached.clone());
+        }
+        if let Some((_, expired)) = entries.responses.remove(key) {
+            entries.bytes -= expired.body.len();
+        }
+        None
+    }
+
+    /// The number of lookups that found a response and of those that did not
+    pub fn hits_and_misses(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Keeps a response, the oldest responses are evicted to stay within the limits.
+    /// Responses larger than the whole cache are not kept.
+    pub fn insert(&self, key: String, response: CachedResponse) {
+        if self.config.max_entries == 0 || response.body.len() > self.config.max_bytes {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if let Some((_, previous)) = entries.responses.remove(&key) {
+            entries.bytes -= previous.body.len();
+        }
+        while entries.responses.len() >= self.config.max_entries
+            || entries.bytes + response.body.len() > self.config.max_bytes
+        {
+            let Some(oldest) = entries
+                .responses
+                .iter()
+                .min_by_key(|(_, (order, _))| *order)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some((_, evicted)) = entries.responses.remove(&oldest) {
+                entries.bytes -= evicted.body.len();
+            }
+        }
+        entries.bytes += response.body.len();
+        let order = entries.next_order;
+        entries.next_order += 1;
+        entries.responses.insert(key, (order, response));
+    }
+
+    /// The number of responses kept
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().responses.len()
+    }
+
+    /// Whether no response is kept
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Middleware serving the GET requests from the cache, the other requests and the errors
+/// are passed through untouched.
+pub async fn cache_responses(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if req.method() != Method::GET {
+        return next.run(req).await;
+    }
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let format = crate::common::ResponseFormat::from_headers(req.headers());
+    let version = snapshot_version(&state);
+    let key = format!(
+        "{}?{}|{}|{}",
+        req.uri().path(),
+        normalize_query(req.uri().query().unwrap_or_default()),
+        format.mime(),
+        version
+    );
+    if let Some(cached) = state.cache.get(&key) {
+        tracing::debug!("Cache hit: {}", key);
+        return cached.to_response(if_none_match.as_deref(), "hit");
+    }
+    let response = next.run(req).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => return crate::error::Error::Axum(e).into_response(),
+    };
+    let cached = CachedResponse::new(parts.headers, body);
+    state.cache.insert(key, cached.clone());
+    cached.to_response(if_none_match.as_deref(), "miss")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &'static str) -> CachedResponse {
+        CachedResponse::new(HeaderMap::new(), Bytes::from_static(body.as_bytes()))
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(
+            normalize_query("title=Alcyone&player=&race=zerg"),
+            normalize_query("race=zerg&title=Alcyone")
+        );
+        assert_ne!(normalize_query("race=zerg"), normalize_query("race=terran"));
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let cached = response("{}");
+        assert!(etag_matches(&cached.etag, &cached.etag));
+        assert!(etag_matches(
+            &format!("\"a\", W/{}", cached.etag),
+            &cached.etag
+        ));
+        assert!(etag_matches("*", &cached.etag));
+        assert!(!etag_matches("\"a\"", &cached.etag));
+        assert_ne!(cached.etag, response("[]").etag);
+        let not_modified = cached.to_response(Some(&cached.etag), "hit");
+        assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn test_data_etag_ignores_meta() {
+        let mut json = HeaderMap::new();
+        json.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let etag = |headers: &HeaderMap, body: &'static str| {
+            data_etag(headers, &Bytes::from_static(body.as_bytes()))
+        };
+        assert_eq!(
+            etag(&json, r#"{"meta":{"duration":12},"data":[1]}"#),
+            etag(&json, r#"{"meta":{"duration":34},"data":[1]}"#)
+        );
+        assert_ne!(
+            etag(&json, r#"{"meta":{"duration":12},"data":[1]}"#),
+            etag(&json, r#"{"meta":{"duration":12},"data":[2]}"#)
+        );
+        // The other formats are tagged by their whole body.
+        assert_ne!(
+            etag(&HeaderMap::new(), r#"{"meta":{"duration":12},"data":[1]}"#),
+            etag(&HeaderMap::new(), r#"{"meta":{"duration":34},"data":[1]}"#)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_version_covers_all_tables() {
+        use crate::store::MemoryStore;
+        use polars::prelude::*;
+        use std::sync::Arc;
+        let df = df!("ext_fs_id" => [1u64]).unwrap();
+        let store = MemoryStore::new().with_table(Table::Details, df.clone());
+        let details_only = snapshot_version(&AppState::new("", Arc::new(store.clone())));
+        let with_unit_born = snapshot_version(&AppState::new(
+            "",
+            Arc::new(store.with_table(Table::UnitBorn, df)),
+        ));
+        assert_ne!(details_only, with_unit_born);
+    }
+
+    #[test]
+    fn test_response_cache_limits() {
+        let cache = ResponseCache::new(CacheConfig {
+            max_entries: 2,
+            max_bytes: 8,
+            ttl: Duration::from_secs(60),
+        });
+        cache.insert("a".to_string(), response("1234"));
+        cache.insert("b".to_string(), response("1234"));
+        cache.insert("c".to_string(), response("12"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.hits_and_misses(), (2, 1));
+        cache.insert("d".to_string(), response("123456789"));
+        assert!(cache.get("d").is_none());
+
+        let expired = ResponseCache::new(CacheConfig {
+            ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        expired.insert("a".to_string(), response("1234"));
+        assert!(expired.get("a").is_none());
+        assert!(expired.is_empty());
+    }
+}
@@ -1,74 +1,451 @@
-use clap::Parser;
+use crate::api::client::ApiClient;
+use crate::api::v1::filter::ReplayFilter;
+use crate::config::{Config, LogFormat, LogRotation};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use eframe::egui;
-use tokio::signal;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
+    /// What to run, starts the server and the native app by default
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// A TOML configuration file, the flags and the environment variables override it
+    #[arg(short, long, value_name = "FILE", env = "SC2_CONFIG")]
+    pub config: Option<String>,
+
     /// Sets the source of the data, can be a file or directory.
-    #[arg(short, long, value_name = "PATH")]
-    pub source_dir: String,
+    #[arg(short, long, value_name = "PATH", env = "SC2_SOURCE_DIR")]
+    pub source_dir: Option<String>,
+
+    /// The format of the files in the source directory
+    #[arg(long, value_enum, env = "SC2_STORE_FORMAT")]
+    pub store_format: Option<crate::store::StoreFormat>,
+
+    /// A JSON file of player aliases, each alias merges several toon handles,
+    /// i.e. `{"Serral": ["2-S2-1-315071", "1-S2-1-1234"]}`
+    #[arg(long, value_name = "FILE", env = "SC2_ALIASES")]
+    pub aliases: Option<String>,
+
+    /// The maximum number of API responses kept in the cache, 0 disables it [default: 256]
+    #[arg(long, env = "SC2_CACHE_ENTRIES")]
+    pub cache_entries: Option<usize>,
+
+    /// The maximum size of the cached API responses in megabytes [default: 64]
+    #[arg(long, value_name = "MB", env = "SC2_CACHE_SIZE")]
+    pub cache_size: Option<usize>,
+
+    /// How long the API responses are cached, in seconds [default: 300]
+    #[arg(long, value_name = "SECONDS", env = "SC2_CACHE_TTL")]
+    pub cache_ttl: Option<u64>,
+
+    /// The maximum time a query may run, in seconds [default: 30]
+    #[arg(long, value_name = "SECONDS", env = "SC2_QUERY_TIMEOUT")]
+    pub query_timeout: Option<u64>,
+
+    /// The maximum number of queries running at the same time [default: 4]
+    #[arg(long, env = "SC2_MAX_CONCURRENT_QUERIES")]
+    pub max_concurrent_queries: Option<usize>,
+
+    /// The maximum number of rows returned by the details queries [default: 10000]
+    #[arg(long, env = "SC2_MAX_ROWS")]
+    pub max_rows: Option<u32>,
+
+    /// How long the in-flight requests may take to complete on shutdown, in seconds [default: 10]
+    #[arg(long, value_name = "SECONDS", env = "SC2_SHUTDOWN_GRACE")]
+    pub shutdown_grace: Option<u64>,
+
+    /// The log level or filter directives, i.e. `info,eframe_sc2::query=debug`, `RUST_LOG`
+    /// overrides it [default: info]
+    #[arg(short, long, env = "SC2_LOG_LEVEL")]
+    pub verbosity_level: Option<String>,
 
-    /// Turn debugging information on
-    #[arg(short, long, default_value = "info")]
-    pub verbosity_level: String,
+    /// The format of the log lines [default: text]
+    #[arg(long, value_enum, env = "SC2_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
 
-    /// Sets the IP to listen for the web server
-    #[arg(short, long, default_value = "0.0.0.0")]
-    pub ip: String,
+    /// A file the logs are also written to, rotated files are suffixed with their date
+    #[arg(long, value_name = "PATH", env = "SC2_LOG_FILE")]
+    pub log_file: Option<String>,
 
-    /// Sets the port to listen for the web server
-    #[arg(short, long, default_value = "3000")]
-    pub port: u16,
+    /// How often the log file is rotated [default: daily]
+    #[arg(long, value_enum, env = "SC2_LOG_ROTATION")]
+    pub log_rotation: Option<LogRotation>,
+
+    /// Sets the IP to listen for the web server [default: 0.0.0.0]
+    #[arg(short, long, env = "SC2_IP")]
+    pub ip: Option<String>,
+
+    /// Sets the port to listen for the web server [default: 3000]
+    #[arg(short, long, env = "SC2_PORT")]
+    pub port: Option<u16>,
+
+    /// The directory of the frontend files served by the web server, defaults to the files
+    /// embedded with the `embed-assets` feature, otherwise `dist`
+    #[arg(long, value_name = "PATH", env = "SC2_STATIC_DIR")]
+    pub static_dir: Option<String>,
+
+    /// Tunnels the HTTP CONNECT requests to the allowed targets or to the upstream
+    #[arg(long, env = "SC2_ENABLE_PROXY")]
+    pub enable_proxy: bool,
+
+    /// A target the proxy may tunnel to, `host:port` or `host:*`, can be repeated
+    #[arg(
+        long,
+        value_name = "HOST:PORT",
+        env = "SC2_PROXY_ALLOW",
+        value_delimiter = ','
+    )]
+    pub proxy_allow: Vec<String>,
+
+    /// The address all the proxy tunnels go to, i.e. the trunk dev server
+    #[arg(long, value_name = "HOST:PORT", env = "SC2_PROXY_UPSTREAM")]
+    pub proxy_upstream: Option<String>,
 
     /// Disables the native server in case we want to run only the web server
-    #[arg(short, long, default_value = "false")]
+    #[arg(short, long, env = "SC2_DISABLE_NATIVE")]
     pub disable_native: bool,
+
+    /// Disables the SQL endpoint
+    #[arg(long, env = "SC2_DISABLE_SQL")]
+    pub disable_sql: bool,
+
+    /// The URL of the backend for the native app, defaults to the local server
+    #[arg(short, long, value_name = "URL", env = "SC2_BACKEND_URL")]
+    pub backend_url: Option<String>,
+}
+
+impl Cli {
+    /// The effective configuration, the config file overridden by the environment variables
+    /// and the flags.
+    pub fn config(&self) -> Result<Config, crate::error::Error> {
+        let mut config = match &self.config {
+            Some(path) => Config::from_file(path)?,
+            None => Config::default(),
+        };
+        fn set<T: Clone>(setting: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *setting = value.clone();
+            }
+        }
+        if self.source_dir.is_some() {
+            config.snapshot.source_dir = self.source_dir.clone();
+        }
+        set(&mut config.snapshot.store_format, &self.store_format);
+        if self.aliases.is_some() {
+            config.snapshot.aliases = self.aliases.clone();
+        }
+        set(&mut config.server.ip, &self.ip);
+        set(&mut config.server.port, &self.port);
+        if self.static_dir.is_some() {
+            config.server.static_dir = self.static_dir.clone();
+        }
+        set(&mut config.server.shutdown_grace, &self.shutdown_grace);
+        if self.backend_url.is_some() {
+            config.server.backend_url = self.backend_url.clone();
+        }
+        if self.enable_proxy {
+            config.proxy.enabled = true;
+        }
+        if !self.proxy_allow.is_empty() {
+            config.proxy.allow = self.proxy_allow.clone();
+        }
+        if self.proxy_upstream.is_some() {
+            config.proxy.upstream = self.proxy_upstream.clone();
+        }
+        set(&mut config.query.timeout, &self.query_timeout);
+        set(
+            &mut config.query.max_concurrent,
+            &self.max_concurrent_queries,
+        );
+        set(&mut config.query.max_rows, &self.max_rows);
+        set(&mut config.cache.entries, &self.cache_entries);
+        set(&mut config.cache.size_mb, &self.cache_size);
+        set(&mut config.cache.ttl, &self.cache_ttl);
+        set(&mut config.log.level, &self.verbosity_level);
+        set(&mut config.log.format, &self.log_format);
+        if self.log_file.is_some() {
+            config.log.file = self.log_file.clone();
+        }
+        set(&mut config.log.rotation, &self.log_rotation);
+        if self.disable_native {
+            config.features.native = false;
+        }
+        if self.disable_sql {
+            config.features.sql = false;
+        }
+        Ok(config)
+    }
+}
+
+impl Cli {
+    /// Sets the environment the command expects. Setting the environment is not thread safe,
+    /// this must run before any thread is started, i.e. before the tokio runtime.
+    pub fn set_env(&self) {
+        let prints_table = self
+            .command
+            .as_ref()
+            .and_then(Command::output)
+            .is_some_and(|output| output.format == OutputFormat::Table);
+        // All the rows are shown, the limit is up to the caller.
+        if prints_table && std::env::var_os("POLARS_FMT_MAX_ROWS").is_none() {
+            std::env::set_var("POLARS_FMT_MAX_ROWS", "-1");
+        }
+    }
+}
+
+/// The subcommands, the query subcommands print their results to stdout.
+#[derive(Subcommand)]
+pub enum Command {
+    /// Starts the server and the native app
+    Serve,
+    /// Prints the maps matching the filters, the most played first
+    Maps(QueryArgs),
+    /// Prints the players matching the filters, the most active first
+    Players(QueryArgs),
+    /// Prints the replays matching the filters, the latest first
+    Replays(QueryArgs),
+    /// Prints the snapshot metadata
+    Stats(OutputArgs),
+    /// Runs a read-only SQL query over the snapshot tables
+    Sql {
+        /// The SELECT statement, i.e. `SELECT COUNT(*) FROM details`
+        query: String,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Inspects the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+impl Command {
+    /// How the results of a query subcommand are printed
+    pub fn output(&self) -> Option<&OutputArgs> {
+        match self {
+            Self::Maps(args) | Self::Players(args) | Self::Replays(args) => Some(&args.output),
+            Self::Stats(output) | Self::Sql { output, .. } => Some(output),
+            Self::Serve | Self::Config { .. } => None,
+        }
+    }
 }
 
-/// Handles the request from the CLI to start the server
+/// The configuration subcommands
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Prints the effective configuration as TOML, after the file, the environment and the flags
+    Show,
+}
+
+/// The output format of the query subcommands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// An aligned table
+    #[default]
+    Table,
+    /// A JSON array of records
+    Json,
+    /// CSV with header, the nested columns are flattened or left out
+    Csv,
+}
+
+/// How the results are printed
+#[derive(Debug, Clone, Args)]
+pub struct OutputArgs {
+    /// The output format
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+
+    /// The maximum number of rows to print
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+}
+
+/// The filters of the query subcommands, these are the query parameters of the HTTP API
+#[derive(Debug, Clone, Default, Args)]
+pub struct FilterArgs {
+    /// The map title
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// The player name
+    #[arg(long)]
+    pub player: Option<String>,
+
+    /// The race of the player, i.e. `zerg`
+    #[arg(long)]
+    pub race: Option<String>,
+
+    /// The result of the player, i.e. `win`
+    #[arg(long)]
+    pub result: Option<String>,
+
+    /// The matchup, i.e. `TvZ`
+    #[arg(long)]
+    pub matchup: Option<String>,
+
+    /// The region of the players, i.e. `eu`
+    #[arg(long)]
+    pub region: Option<String>,
+
+    /// The layout of the teams, i.e. `1v1`
+    #[arg(long)]
+    pub layout: Option<String>,
+
+    /// The first date of the replays, i.e. `2024-06-01`
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+
+    /// The last date of the replays
+    #[arg(long, value_name = "DATE")]
+    pub until: Option<String>,
+
+    /// Any other filter as a query string, i.e. `min_version=5.0.11&ai=include`
+    #[arg(long, value_name = "QUERY")]
+    pub filter: Option<String>,
+}
+
+impl FilterArgs {
+    /// Builds the filter the same way the HTTP handlers do, so the same values are accepted.
+    pub fn to_filter(&self) -> Result<ReplayFilter, crate::error::Error> {
+        let mut pairs: Vec<(String, String)> = match &self.filter {
+            Some(query) => serde_urlencoded::from_str(query)
+                .map_err(|e| crate::error::Error::BadRequest(e.to_string()))?,
+            None => vec![],
+        };
+        let options = [
+            ("title", &self.title),
+            ("player", &self.player),
+            ("race", &self.race),
+            ("result", &self.result),
+            ("matchup", &self.matchup),
+            ("region", &self.region),
+            ("layout", &self.layout),
+            ("file_min_date", &self.since),
+            ("file_max_date", &self.until),
+        ];
+        for (name, value) in options {
+            if let Some(value) = value {
+                pairs.retain(|(key, _)| key != name);
+                pairs.push((name.to_string(), value.clone()));
+            }
+        }
+        let query = serde_urlencoded::to_string(pairs)
+            .map_err(|e| crate::error::Error::BadRequest(e.to_string()))?;
+        let filter: ReplayFilter = serde_urlencoded::from_str(&query)
+            .map_err(|e| crate::error::Error::BadRequest(e.to_string()))?;
+        Ok(filter.validate()?)
+    }
+}
+
+/// The arguments of the subcommands that query the replays
+#[derive(Debug, Clone, Args)]
+pub struct QueryArgs {
+    #[command(flatten)]
+    pub filter: FilterArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+/// Handles the request from the CLI, either starts the server or runs a query subcommand.
 /// And initializes the logging system
-pub async fn process_cli_request() -> Result<(), crate::error::Error> {
-    let cli = Cli::parse();
-    // use the cli verbosity level to set the tracing level
-    let level = match cli.verbosity_level.as_str() {
-        "error" => tracing::Level::ERROR,
-        "warn" => tracing::Level::WARN,
-        "info" => tracing::Level::INFO,
-        "debug" => tracing::Level::DEBUG,
-        "trace" => tracing::Level::TRACE,
-        _ => tracing::Level::INFO,
-    };
-
-    tracing_subscriber::fmt()
-        .with_max_level(level)
-        .with_env_filter("info")
-        .init();
-
-    crate::server::start_server(&cli).await;
+pub async fn process_cli_request(cli: Cli) -> Result<(), crate::error::Error> {
+    let config = cli.config()?;
+    // Flushes the log file on exit.
+    let _log_guard = crate::logging::init(&config.log)?;
+
+    match &cli.command {
+        None | Some(Command::Serve) => serve(&config).await,
+        Some(command) => crate::commands::run_command(&config, command).await,
+    }
+}
+
+/// Starts the server and, unless disabled, the native app
+async fn serve(config: &Config) -> Result<(), crate::error::Error> {
+    let server = crate::server::spawn_server(config).await?;
 
     tracing::info!(
         "Starting SC2ReplayExplorer with source directory: {}",
-        cli.source_dir
+        config.source_dir()?
     );
-    if !cli.disable_native {
+    if !config.features.native {
+        crate::server::shutdown_signal().await;
+    } else {
         let native_options = eframe::NativeOptions {
             viewport: egui::ViewportBuilder::default().with_inner_size([320.0, 240.0]),
             ..Default::default()
         };
+        let backend_url = config.server.native_backend_url();
+        tracing::info!("Native app using backend: {}", backend_url);
         eframe::run_native(
             "eframe sc2",
             native_options,
-            Box::new(|cc| Ok(Box::new(crate::SC2ReplayExplorer::new(cc)))),
-        )
-        .unwrap();
-    }
-    match signal::ctrl_c().await {
-        Ok(()) => {}
-        Err(err) => {
-            eprintln!("Unable to listen for shutdown signal: {err:?}");
-            // we also shut down in case of error
-        }
+            Box::new(|cc| {
+                // A signal closes the window, the server is stopped once it is closed.
+                let ctx = cc.egui_ctx.clone();
+                tokio::spawn(async move {
+                    crate::server::shutdown_signal().await;
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                });
+                Ok(Box::new(crate::SC2ReplayExplorer::with_api_client(
+                    cc,
+                    ApiClient::new(backend_url),
+                )))
+            }),
+        )?;
     }
+    server.shutdown().await;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_args_to_filter() {
+        let args = FilterArgs {
+            title: Some("Alcyone".to_string()),
+            since: Some("2024-06-01".to_string()),
+            filter: Some("title=Other&min_version=5.0.11".to_string()),
+            ..Default::default()
+        };
+        let filter = args.to_filter().unwrap();
+        assert_eq!(filter.title, "Alcyone");
+        assert_eq!(filter.min_version, "5.0.11");
+        assert_eq!(
+            filter.file_min_date,
+            chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+        );
+        // The values are decoded once, a literal `%41` is kept.
+        let percent = FilterArgs {
+            player: Some("100%41".to_string()),
+            filter: Some("file_name=a%2541.SC2Replay".to_string()),
+            ..Default::default()
+        };
+        let filter = percent.to_filter().unwrap();
+        assert_eq!(filter.player, "100%41");
+        assert_eq!(filter.file_name, "a%41.SC2Replay");
+        let invalid = FilterArgs {
+            race: Some("elf".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.to_filter().is_err());
+    }
+
+    #[test]
+    fn test_cli_overrides_config() {
+        let cli = Cli::try_parse_from(["eframe_sc2", "-s", "ipcs", "-p", "8080", "--disable-sql"])
+            .unwrap();
+        let config = cli.config().unwrap();
+        assert_eq!(config.source_dir().unwrap(), "ipcs");
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.server.ip, "0.0.0.0");
+        assert!(!config.features.sql);
+        assert!(config.features.native);
+    }
+}
@@ -0,0 +1,84 @@
+//! The headless subcommands, they run the same queries as the HTTP handlers and print the
+//! results to stdout.
+
+use crate::api::v1::details::{maps, players, replays};
+use crate::api::v1::sql::SqlQueryReq;
+use crate::cli::{Command, ConfigCommand, OutputArgs, OutputFormat};
+use crate::config::Config;
+use crate::server::AppState;
+use polars::prelude::*;
+use std::io::Write;
+
+/// Prints the DataFrame to stdout in the requested format
+pub fn print_df(df: DataFrame, output: &OutputArgs) -> Result<(), crate::error::Error> {
+    let mut df = match output.limit {
+        Some(limit) => df.head(Some(limit)),
+        None => df,
+    };
+    let mut buf = Vec::new();
+    match output.format {
+        // All the rows are shown, see `Cli::set_env`.
+        OutputFormat::Table => writeln!(buf, "{}", df)?,
+        OutputFormat::Json => {
+            crate::common::write_df_json(&mut df, &mut buf)?;
+            buf.push(b'\n');
+        }
+        OutputFormat::Csv => crate::common::write_df_csv(df, &mut buf)?,
+    }
+    std::io::stdout().lock().write_all(&buf)?;
+    Ok(())
+}
+
+/// The snapshot metadata as a single row
+async fn snapshot_stats_df(state: AppState) -> Result<DataFrame, crate::error::Error> {
+    let stats = crate::api::v1::snapshot_stats::dataframe::get_metadata(state).await?;
+    let date_modified = chrono::DateTime::<chrono::Utc>::from(stats.date_modified).to_rfc3339();
+    Ok(df!(
+        "directory" => [stats.directory],
+        "directory_size" => [stats.directory_size],
+        "date_modified" => [date_modified],
+    )?)
+}
+
+/// Runs a query subcommand
+pub async fn run_command(config: &Config, command: &Command) -> Result<(), crate::error::Error> {
+    if let Command::Config {
+        action: ConfigCommand::Show,
+    } = command
+    {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+    let state = AppState::from_config(config)?;
+    match command {
+        Command::Serve | Command::Config { .. } => Err(crate::error::Error::Other(
+            "Not a query subcommand".to_string(),
+        )),
+        Command::Maps(args) => {
+            let res = maps::dataframe::query_map_freq(args.filter.to_filter()?, state).await?;
+            print_df(res, &args.output)
+        }
+        Command::Players(args) => {
+            let res =
+                players::dataframe::query_player_freq(args.filter.to_filter()?, state).await?;
+            print_df(res, &args.output)
+        }
+        Command::Replays(args) => {
+            let res = replays::dataframe::query_replays(args.filter.to_filter()?, state).await?;
+            print_df(res, &args.output)
+        }
+        Command::Stats(output) => print_df(snapshot_stats_df(state).await?, output),
+        Command::Sql { query, output } => {
+            let req = SqlQueryReq {
+                query: query.clone(),
+                limit: output
+                    .limit
+                    .map_or_else(SqlQueryReq::default_limit, |limit| {
+                        u32::try_from(limit).unwrap_or(u32::MAX)
+                    }),
+            };
+            let res = crate::api::v1::sql::dataframe::query_sql(req, state).await?;
+            print_df(res, output)
+        }
+    }
+}
@@ -5,11 +5,252 @@
 use polars::prelude::*;
 
 #[cfg(not(target_arch = "wasm32"))]
-/// Converts a Dataframe into a String, this is expensive but useful for small results.
-pub fn convert_df_to_json_data(df: &DataFrame) -> Result<String, crate::error::Error> {
-    let mut buf = Vec::new();
-    JsonWriter::new(&mut buf)
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// The media type of Arrow IPC streams
+pub const ARROW_STREAM_MIME: &str = "application/vnd.apache.arrow.stream";
+/// The media type of CSV documents
+pub const CSV_MIME: &str = "text/csv";
+/// The media type of JSON documents
+pub const JSON_MIME: &str = "application/json";
+
+/// The serialization formats available for DataFrame results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseFormat {
+    /// A JSON object with `meta` and `data` keys.
+    #[default]
+    Json,
+    /// An Arrow IPC stream, the metadata is sent in the `x-meta-*` headers.
+    ArrowIpc,
+    /// A CSV document with header, the metadata is sent in the `x-meta-*` headers.
+    Csv,
+}
+
+impl ResponseFormat {
+    /// Picks the supported format with the highest `q` weight from an `Accept` header value,
+    /// the first one listed on ties. `*/*` stands for JSON, the default, and the `q=0` types
+    /// are never picked.
+    pub fn from_accept(accept: &str) -> Self {
+        let mut best: Option<(f32, Self)> = None;
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';');
+            let media_type = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            let format = match media_type.as_str() {
+                ARROW_STREAM_MIME => Self::ArrowIpc,
+                CSV_MIME => Self::Csv,
+                JSON_MIME | "*/*" => Self::Json,
+                _ => continue,
+            };
+            let q = params
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+                .map_or(Some(1.0), |(_, value)| value.trim().parse::<f32>().ok());
+            let Some(q) = q.filter(|q| *q > 0.0) else {
+                continue;
+            };
+            if best.is_none_or(|(best_q, _)| q > best_q) {
+                best = Some((q, format));
+            }
+        }
+        best.map(|(_, format)| format).unwrap_or_default()
+    }
+
+    /// The Content-Type of the format
+    pub fn mime(&self) -> &'static str {
+        match self {
+            Self::Json => JSON_MIME,
+            Self::ArrowIpc => ARROW_STREAM_MIME,
+            Self::Csv => CSV_MIME,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ResponseFormat {
+    /// Negotiates the format from the request headers.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .map(Self::from_accept)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Writes a DataFrame as a JSON array of records.
+pub fn write_df_json(df: &mut DataFrame, buf: &mut Vec<u8>) -> Result<(), crate::error::Error> {
+    JsonWriter::new(buf)
         .with_json_format(JsonFormat::Json)
-        .finish(&mut df.clone())?;
-    Ok(String::from_utf8(buf)?)
+        .finish(df)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Converts a Dataframe into a list of records, this is expensive but useful for small results.
+pub fn df_to_records<T: serde::de::DeserializeOwned>(
+    df: &DataFrame,
+) -> Result<Vec<T>, crate::error::Error> {
+    let mut buf = Vec::new();
+    write_df_json(&mut df.clone(), &mut buf)?;
+    Ok(serde_json::from_slice(&buf)?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// CSV does not support nested types, the list columns are joined with commas and the struct
+/// columns, i.e. the name history of the players, are left out.
+fn flatten_list_columns(df: DataFrame) -> Result<DataFrame, crate::error::Error> {
+    let schema = df.schema();
+    let is_struct = |dtype: &DataType| match dtype {
+        DataType::List(inner) => matches!(**inner, DataType::Struct(_) | DataType::List(_)),
+        dtype => matches!(dtype, DataType::Struct(_)),
+    };
+    let struct_columns: Vec<PlSmallStr> = schema
+        .iter()
+        .filter(|(_, dtype)| is_struct(dtype))
+        .map(|(name, _)| name.clone())
+        .collect();
+    let list_columns: Vec<Expr> = schema
+        .iter()
+        .filter(|(_, dtype)| matches!(dtype, DataType::List(_)) && !is_struct(dtype))
+        .map(|(name, _)| {
+            col(name.clone())
+                .cast(DataType::List(Box::new(DataType::String)))
+                .list()
+                .join(lit(","), true)
+        })
+        .collect();
+    if list_columns.is_empty() && struct_columns.is_empty() {
+        return Ok(df);
+    }
+    Ok(df
+        .drop_many(struct_columns)
+        .lazy()
+        .with_columns(list_columns)
+        .collect()?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Writes a DataFrame as CSV with header, see `flatten_list_columns`.
+pub fn write_df_csv(df: DataFrame, buf: &mut Vec<u8>) -> Result<(), crate::error::Error> {
+    let mut df = flatten_list_columns(df)?;
+    CsvWriter::new(buf).include_header(true).finish(&mut df)?;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// A DataFrame result serialized in the format negotiated with the client.
+pub struct DataFrameResponse {
+    /// The metadata of the response
+    pub meta: crate::meta::ResponseMeta,
+    /// The result of the query
+    pub df: DataFrame,
+    /// The format requested by the client
+    pub format: ResponseFormat,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DataFrameResponse {
+    /// Creates a new DataFrameResponse, the total of the meta is set to the DataFrame height.
+    pub fn new(
+        meta: crate::meta::ResponseMetaBuilder,
+        df: DataFrame,
+        format: ResponseFormat,
+    ) -> Self {
+        Self {
+            meta: meta.with_total(df.height()).build(),
+            df,
+            format,
+        }
+    }
+
+    /// Serializes the response body.
+    fn into_body(mut self) -> Result<Vec<u8>, crate::error::Error> {
+        let mut buf = Vec::new();
+        match self.format {
+            ResponseFormat::Json => {
+                // The DataFrame is written directly into the response, this
+                // avoids parsing it back into the typed records.
+                buf.extend_from_slice(b"{\"meta\":");
+                serde_json::to_writer(&mut buf, &self.meta)?;
+                buf.extend_from_slice(b",\"data\":");
+                write_df_json(&mut self.df, &mut buf)?;
+                buf.push(b'}');
+            }
+            ResponseFormat::ArrowIpc => {
+                IpcStreamWriter::new(&mut buf).finish(&mut self.df)?;
+            }
+            ResponseFormat::Csv => write_df_csv(self.df, &mut buf)?,
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl IntoResponse for DataFrameResponse {
+    fn into_response(self) -> Response {
+        let format = self.format;
+        let meta = self.meta.clone();
+        match self.into_body() {
+            Ok(body) => {
+                let mut response = (
+                    StatusCode::OK,
+                    [(
+                        header::CONTENT_TYPE,
+                        HeaderValue::from_static(format.mime()),
+                    )],
+                    body,
+                )
+                    .into_response();
+                let headers = response.headers_mut();
+                headers.insert("x-meta-total", HeaderValue::from(meta.total));
+                headers.insert("x-meta-epoch", HeaderValue::from(meta.epoch));
+                headers.insert("x-meta-duration", HeaderValue::from(meta.duration));
+                response
+            }
+            Err(e) => e.into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_format_from_accept() {
+        assert_eq!(ResponseFormat::from_accept(""), ResponseFormat::Json);
+        assert_eq!(ResponseFormat::from_accept("*/*"), ResponseFormat::Json);
+        assert_eq!(
+            ResponseFormat::from_accept("text/csv;q=0.9, application/json"),
+            ResponseFormat::Json
+        );
+        assert_eq!(
+            ResponseFormat::from_accept("application/json;q=0.5, text/csv;q=0.8"),
+            ResponseFormat::Csv
+        );
+        assert_eq!(
+            ResponseFormat::from_accept("text/csv, application/json"),
+            ResponseFormat::Csv
+        );
+        assert_eq!(
+            ResponseFormat::from_accept("text/csv;q=0, text/html"),
+            ResponseFormat::Json
+        );
+        assert_eq!(
+            ResponseFormat::from_accept("text/csv;q=0.2, */*;q=0.1"),
+            ResponseFormat::Csv
+        );
+        assert_eq!(
+            ResponseFormat::from_accept("application/vnd.apache.arrow.stream"),
+            ResponseFormat::ArrowIpc
+        );
+    }
 }
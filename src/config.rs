@@ -0,0 +1,297 @@
+//! Layered configuration of the server and the subcommands.
+//!
+//! The settings are read from a TOML file, then from the `SC2_*` environment variables, then
+//! from the command line flags, each layer overriding the previous one. The environment
+//! variables and the flags are handled by clap, see `cli::Cli`.
+
+use crate::cache::CacheConfig;
+use crate::query::QueryLimits;
+use crate::store::StoreFormat;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The snapshot to query
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotSection {
+    /// The directory of the snapshot files
+    pub source_dir: Option<String>,
+    /// The format of the files in the source directory
+    pub store_format: StoreFormat,
+    /// A JSON file of player aliases
+    pub aliases: Option<String>,
+}
+
+/// The HTTP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSection {
+    /// The IP to listen on
+    pub ip: String,
+    /// The port to listen on
+    pub port: u16,
+    /// The directory of the frontend files, defaults to the embedded files or `dist`
+    pub static_dir: Option<String>,
+    /// How long the in-flight requests may take to complete on shutdown, in seconds
+    pub shutdown_grace: u64,
+    /// The URL of the backend for the native app, defaults to the local server
+    pub backend_url: Option<String>,
+}
+
+impl Default for ServerSection {
+    fn default() -> Self {
+        Self {
+            ip: "0.0.0.0".to_string(),
+            port: 3000,
+            static_dir: None,
+            shutdown_grace: 10,
+            backend_url: None,
+        }
+    }
+}
+
+impl ServerSection {
+    /// The URL the native app uses to reach the backend.
+    /// When listening on all interfaces the loopback address is used.
+    pub fn native_backend_url(&self) -> String {
+        if let Some(backend_url) = &self.backend_url {
+            return backend_url.clone();
+        }
+        let host = match self.ip.as_str() {
+            "0.0.0.0" => "127.0.0.1",
+            "::" => "[::1]",
+            ip if ip.contains(':') => return format!("http://[{}]:{}", ip, self.port),
+            ip => ip,
+        };
+        format!("http://{}:{}", host, self.port)
+    }
+}
+
+/// The HTTP `CONNECT` proxy, disabled by default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxySection {
+    /// Whether the `CONNECT` requests are tunneled
+    pub enabled: bool,
+    /// The allowed targets, `host:port` or `host:*`
+    pub allow: Vec<String>,
+    /// The address all the tunnels go to, i.e. the trunk dev server, the allowlist is ignored
+    pub upstream: Option<String>,
+}
+
+/// The limits of the queries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuerySection {
+    /// The maximum time a query may run, in seconds
+    pub timeout: u64,
+    /// The maximum number of queries running at the same time
+    pub max_concurrent: usize,
+    /// The maximum number of rows returned by the details queries
+    pub max_rows: u32,
+}
+
+impl Default for QuerySection {
+    fn default() -> Self {
+        let limits = QueryLimits::default();
+        Self {
+            timeout: limits.timeout.as_secs(),
+            max_concurrent: limits.max_concurrent,
+            max_rows: limits.max_rows,
+        }
+    }
+}
+
+/// The response cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSection {
+    /// The maximum number of responses kept, 0 disables the cache
+    pub entries: usize,
+    /// The maximum size of the cached responses in megabytes
+    pub size_mb: usize,
+    /// How long the responses are cached, in seconds
+    pub ttl: u64,
+}
+
+impl Default for CacheSection {
+    fn default() -> Self {
+        let cache = CacheConfig::default();
+        Self {
+            entries: cache.max_entries,
+            size_mb: cache.max_bytes / (1024 * 1024),
+            ttl: cache.ttl.as_secs(),
+        }
+    }
+}
+
+/// The format of the log lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// How often the log file is rotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    /// A new file every hour
+    Hourly,
+    /// A new file every day
+    #[default]
+    Daily,
+    /// A single file
+    Never,
+}
+
+/// The logs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogSection {
+    /// The level or the filter directives, i.e. `info,eframe_sc2::query=debug`
+    pub level: String,
+    /// The format of the lines
+    pub format: LogFormat,
+    /// A file the logs are also written to
+    pub file: Option<String>,
+    /// How often the log file is rotated
+    pub rotation: LogRotation,
+}
+
+impl Default for LogSection {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::default(),
+            file: None,
+            rotation: LogRotation::default(),
+        }
+    }
+}
+
+/// The optional parts of the application
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureSection {
+    /// Starts the native app with the server
+    pub native: bool,
+    /// Serves the `/api/v1/sql` endpoint
+    pub sql: bool,
+}
+
+impl Default for FeatureSection {
+    fn default() -> Self {
+        Self {
+            native: true,
+            sql: true,
+        }
+    }
+}
+
+/// The effective configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The snapshot to query
+    pub snapshot: SnapshotSection,
+    /// The HTTP server
+    pub server: ServerSection,
+    /// The HTTP `CONNECT` proxy
+    pub proxy: ProxySection,
+    /// The limits of the queries
+    pub query: QuerySection,
+    /// The response cache
+    pub cache: CacheSection,
+    /// The logs
+    pub log: LogSection,
+    /// The optional parts of the application
+    pub features: FeatureSection,
+}
+
+impl Config {
+    /// Reads a TOML configuration file, the missing settings keep their default.
+    pub fn from_file(path: &str) -> Result<Self, crate::error::Error> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_toml(&contents)
+            .map_err(|e| crate::error::Error::Config(format!("Invalid config {}: {}", path, e)))
+    }
+
+    /// Parses a TOML configuration
+    pub fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// The configuration as TOML
+    pub fn to_toml(&self) -> Result<String, crate::error::Error> {
+        toml::to_string_pretty(self).map_err(|e| crate::error::Error::Other(e.to_string()))
+    }
+
+    /// The snapshot directory, it has no default.
+    pub fn source_dir(&self) -> Result<&str, crate::error::Error> {
+        self.snapshot.source_dir.as_deref().ok_or_else(|| {
+            crate::error::Error::Config(
+                "The snapshot directory is not set, use --source-dir, SC2_SOURCE_DIR or \
+                 snapshot.source_dir in the config file"
+                    .to_string(),
+            )
+        })
+    }
+
+    /// The limits of the queries
+    pub fn query_limits(&self) -> QueryLimits {
+        QueryLimits {
+            timeout: Duration::from_secs(self.query.timeout),
+            max_concurrent: self.query.max_concurrent,
+            max_rows: self.query.max_rows,
+        }
+    }
+
+    /// The limits of the response cache
+    pub fn cache_config(&self) -> CacheConfig {
+        CacheConfig {
+            max_entries: self.cache.entries,
+            max_bytes: self.cache.size_mb * 1024 * 1024,
+            ttl: Duration::from_secs(self.cache.ttl),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_toml() {
+        let config = Config::from_toml(
+            r#"
+            [snapshot]
+            source_dir = "ipcs"
+            store_format = "parquet"
+
+            [server]
+            port = 8080
+
+            [features]
+            sql = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.snapshot.source_dir.as_deref(), Some("ipcs"));
+        assert_eq!(config.snapshot.store_format, StoreFormat::Parquet);
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.server.ip, "0.0.0.0");
+        assert!(!config.features.sql);
+        assert!(config.features.native);
+        assert!(Config::from_toml("[server]\nprot = 1").is_err());
+        // The shown configuration can be read back.
+        assert_eq!(
+            Config::from_toml(&config.to_toml().unwrap()).unwrap(),
+            config
+        );
+    }
+}
@@ -1,6 +1,11 @@
 //! Handling of Error Messages
 //!
 
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use polars::error::PolarsError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -19,6 +24,150 @@ pub enum Error {
     Eframe(#[from] eframe::Error),
     #[error("UTF8 Error: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
+    #[error("Bad Request: {0}")]
+    BadRequest(String),
+    #[error("Invalid Parameters: {0}")]
+    Validation(#[from] crate::api::validation::FieldErrors),
+    #[error("Not Found: {0}")]
+    NotFound(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Unprocessable: {0}")]
+    Unprocessable(String),
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+    #[error("Config Error: {0}")]
+    Config(String),
     #[error("Other Error: {0}")]
     Other(String),
 }
+
+/// The error codes sent to the clients, these are stable and can be matched on.
+pub mod code {
+    /// The request parameters could not be parsed.
+    pub const BAD_REQUEST: &str = "bad_request";
+    /// The request parameters were parsed but some fields are invalid.
+    pub const INVALID_PARAMS: &str = "invalid_params";
+    /// A snapshot file or the requested resource does not exist.
+    pub const NOT_FOUND: &str = "not_found";
+    /// The snapshot files do not have the columns or types the queries expect.
+    pub const SCHEMA_MISMATCH: &str = "schema_mismatch";
+    /// The request was understood but can not be processed, i.e. invalid SQL.
+    pub const UNPROCESSABLE: &str = "unprocessable";
+    /// The server is temporarily unable to answer, i.e. a query timed out.
+    pub const UNAVAILABLE: &str = "unavailable";
+    /// Any other error.
+    pub const INTERNAL: &str = "internal";
+}
+
+impl Error {
+    /// The HTTP status code for the error
+    pub fn status_code(&self) -> StatusCode {
+        match self.code() {
+            code::BAD_REQUEST | code::INVALID_PARAMS => StatusCode::BAD_REQUEST,
+            code::NOT_FOUND => StatusCode::NOT_FOUND,
+            code::SCHEMA_MISMATCH => StatusCode::CONFLICT,
+            code::UNPROCESSABLE => StatusCode::UNPROCESSABLE_ENTITY,
+            code::UNAVAILABLE => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The machine readable code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(e) if e.kind() == std::io::ErrorKind::NotFound => code::NOT_FOUND,
+            Self::Polars(e) => polars_error_code(e),
+            Self::BadRequest(_) => code::BAD_REQUEST,
+            Self::Validation(_) => code::INVALID_PARAMS,
+            Self::NotFound(_) => code::NOT_FOUND,
+            Self::Conflict(_) => code::SCHEMA_MISMATCH,
+            Self::Unprocessable(_) => code::UNPROCESSABLE,
+            Self::Unavailable(_) => code::UNAVAILABLE,
+            _ => code::INTERNAL,
+        }
+    }
+}
+
+/// Classifies the polars errors, missing files and schema differences are common when the
+/// snapshot is incomplete or was written by another version of s2protocol.
+fn polars_error_code(e: &PolarsError) -> &'static str {
+    match e {
+        PolarsError::IO { error, .. } if error.kind() == std::io::ErrorKind::NotFound => {
+            code::NOT_FOUND
+        }
+        PolarsError::ColumnNotFound(_)
+        | PolarsError::SchemaFieldNotFound(_)
+        | PolarsError::SchemaMismatch(_)
+        | PolarsError::StructFieldNotFound(_) => code::SCHEMA_MISMATCH,
+        PolarsError::SQLInterface(_) | PolarsError::SQLSyntax(_) => code::UNPROCESSABLE,
+        PolarsError::Context { error, .. } => polars_error_code(error),
+        _ => code::INTERNAL,
+    }
+}
+
+impl From<QueryRejection> for Error {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<JsonRejection> for Error {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(_) => Self::Unprocessable(rejection.body_text()),
+            _ => Self::BadRequest(rejection.body_text()),
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        if status.is_server_error() {
+            tracing::error!("Error: {}", self);
+        } else {
+            tracing::warn!("Error: {}", self);
+        }
+        let mut meta =
+            crate::meta::ResponseMetaBuilder::new().with_error_code(self.code(), self.to_string());
+        if let Self::Validation(fields) = self {
+            meta = meta.with_field_errors(fields);
+        }
+        let meta = meta.build();
+        (status, Json(serde_json::json!({ "meta": meta }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_status_code() {
+        let missing_ipc = Error::Polars(PolarsError::from(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "details.ipc",
+        )));
+        assert_eq!(missing_ipc.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(missing_ipc.code(), code::NOT_FOUND);
+        let schema = Error::Polars(PolarsError::ColumnNotFound("ext_fs_id".into()));
+        assert_eq!(schema.status_code(), StatusCode::CONFLICT);
+        assert_eq!(
+            Error::BadRequest("bad date".to_string()).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            Error::Unavailable("timeout".to_string()).status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            Error::Other("boom".to_string()).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            Error::Config("Invalid config".to_string()).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
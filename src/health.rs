@@ -0,0 +1,247 @@
+//! Liveness and readiness of the server
+//!
+//! A snapshot that is incomplete or was written by an incompatible version of s2protocol
+//! would otherwise only fail on the first query. The schema of each table is checked against
+//! the columns the queries use, at startup and on `/readyz`.
+
+use crate::api::v1::filter::columns;
+use crate::server::AppState;
+use crate::store::{ReplayStore, Table};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+/// The columns a table must have
+#[derive(Debug, Clone, Copy)]
+pub struct ExpectedTable {
+    /// The table
+    pub table: Table,
+    /// Whether the queries fail without the table, the optional tables only degrade them
+    pub required: bool,
+    /// The columns the queries use
+    pub columns: &'static [&'static str],
+}
+
+/// The tables checked and the columns the queries use
+pub const EXPECTED_TABLES: &[ExpectedTable] = &[
+    ExpectedTable {
+        table: Table::Details,
+        required: true,
+        columns: &[
+            columns::REPLAY_ID,
+            columns::DATETIME,
+            columns::FILE_NAME,
+            columns::TITLE,
+            columns::PLAYER_NAME,
+            columns::RACE,
+            columns::RESULT,
+            columns::REGION,
+            "player_toon_program_id",
+            "player_toon_realm",
+            "player_toon_id",
+            columns::GAME_VERSION,
+            columns::BASE_BUILD,
+            columns::DURATION,
+            columns::TEAM_ID,
+            columns::CONTROL,
+            columns::WORKING_SET_SLOT_ID,
+        ],
+    },
+    ExpectedTable {
+        table: Table::InitData,
+        required: false,
+        columns: &[
+            columns::init_data::REPLAY_ID,
+            columns::init_data::WORKING_SET_SLOT_ID,
+            columns::init_data::OBSERVE,
+            columns::init_data::DIFFICULTY,
+        ],
+    },
+    ExpectedTable {
+        table: Table::UnitBorn,
+        // Only the tracker events queries read it, they are not routed.
+        required: false,
+        columns: &[
+            "ext_fs_id",
+            "ext_replay_loop",
+            "ext_replay_detail_player_name",
+            "unit_type_name",
+            "x",
+            "y",
+        ],
+    },
+];
+
+/// The state of a table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TableStatus {
+    /// The table has all the expected columns
+    Ok,
+    /// The table file does not exist
+    Missing,
+    /// The table lacks some of the expected columns
+    MissingColumns {
+        /// The expected columns that are not in the table
+        columns: Vec<String>,
+    },
+    /// The table could not be read
+    Unreadable {
+        /// The error reading the table
+        error: String,
+    },
+}
+
+/// The check of a table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableReport {
+    /// The name of the table
+    pub table: String,
+    /// Whether the queries fail without the table
+    pub required: bool,
+    /// The state of the table
+    #[serde(flatten)]
+    pub status: TableStatus,
+}
+
+impl TableReport {
+    /// Whether the table prevents the queries from running
+    pub fn is_blocking(&self) -> bool {
+        match self.status {
+            TableStatus::Ok => false,
+            TableStatus::Missing => self.required,
+            _ => true,
+        }
+    }
+}
+
+/// The check of the snapshot tables
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaReport {
+    /// Whether all the queries can run
+    pub ready: bool,
+    /// The check of each table
+    pub tables: Vec<TableReport>,
+}
+
+impl std::fmt::Display for SchemaReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for report in &self.tables {
+            let status = match &report.status {
+                TableStatus::Ok => "ok".to_string(),
+                TableStatus::Missing if report.required => "missing".to_string(),
+                TableStatus::Missing => "missing, optional".to_string(),
+                TableStatus::MissingColumns { columns } => {
+                    format!("missing columns {}", columns.join(", "))
+                }
+                TableStatus::Unreadable { error } => format!("unreadable: {}", error),
+            };
+            writeln!(f, "  {}: {}", report.table, status)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the expected tables exist and have the columns the queries use.
+/// Only the schemas are read, not the rows.
+pub fn check_schema(store: &dyn ReplayStore) -> SchemaReport {
+    let tables: Vec<TableReport> = EXPECTED_TABLES
+        .iter()
+        .map(|expected| {
+            let schema = store
+                .scan(expected.table)
+                .and_then(|mut scan| scan.collect_schema().map_err(Into::into));
+            let status = match schema {
+                Ok(schema) => {
+                    let missing: Vec<String> = expected
+                        .columns
+                        .iter()
+                        .filter(|column| !schema.contains(column))
+                        .map(|column| column.to_string())
+                        .collect();
+                    if missing.is_empty() {
+                        TableStatus::Ok
+                    } else {
+                        TableStatus::MissingColumns { columns: missing }
+                    }
+                }
+                Err(e) if e.code() == crate::error::code::NOT_FOUND => TableStatus::Missing,
+                Err(e) => TableStatus::Unreadable {
+                    error: e.to_string(),
+                },
+            };
+            TableReport {
+                table: expected.table.to_string(),
+                required: expected.required,
+                status,
+            }
+        })
+        .collect();
+    SchemaReport {
+        ready: !tables.iter().any(TableReport::is_blocking),
+        tables,
+    }
+}
+
+/// Checks the snapshot before serving, the error lists the problems of each table.
+pub fn ensure_schema(state: &AppState) -> Result<(), crate::error::Error> {
+    let report = check_schema(&*state.store);
+    if report.ready {
+        tracing::info!("Snapshot {} schema:\n{}", state.source_dir, report);
+        Ok(())
+    } else {
+        Err(crate::error::Error::Other(format!(
+            "The snapshot {} can not be queried:\n{}",
+            state.source_dir, report
+        )))
+    }
+}
+
+/// Whether the process is alive
+pub async fn route_healthz() -> &'static str {
+    "ok"
+}
+
+/// Whether the snapshot can be queried, `503` with the report otherwise
+pub async fn route_readyz(State(state): State<AppState>) -> Result<Response, crate::error::Error> {
+    let store = state.store.clone();
+    let report = tokio::task::spawn_blocking(move || check_schema(&*store))
+        .await
+        .map_err(|e| crate::error::Error::Other(e.to_string()))?;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((status, Json(report)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use polars::prelude::*;
+
+    #[test]
+    fn test_check_schema() {
+        let details = df!("ext_fs_id" => [1u64], "title" => ["Alcyone LE"]).unwrap();
+        let store = MemoryStore::new().with_table(Table::Details, details);
+        let report = check_schema(&store);
+        assert!(!report.ready);
+        let details = &report.tables[0];
+        assert!(matches!(
+            &details.status,
+            TableStatus::MissingColumns { columns } if columns.contains(&"ext_datetime".to_string())
+        ));
+        // The optional init_data does not block the queries.
+        assert_eq!(report.tables[1].status, TableStatus::Missing);
+        assert!(!report.tables[1].is_blocking());
+        assert!(report
+            .to_string()
+            .contains("unit_born: missing, optional\n"));
+    }
+}
@@ -10,14 +10,44 @@ pub const DETAILS_IPC: &str = "details.ipc";
 pub const INIT_DATA_IPC: &str = "init_data.ipc";
 pub const UNIT_BORN_IPC: &str = "unit_born.ipc";
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod assets;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cache;
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod cli;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod commands;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod config;
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod error;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use error::*;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod health;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod logging;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod metrics;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod proxy;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod query;
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod server;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod store;
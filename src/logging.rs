@@ -0,0 +1,99 @@
+//! Logging setup, the filter directives, the format and the optional rotating log file.
+//!
+//! The filter is taken from `RUST_LOG` when set, otherwise from the configured level, both
+//! accept directives such as `info,eframe_sc2::query=debug`. The logs go to stderr so that the
+//! output of the subcommands can be piped.
+
+use crate::config::{LogFormat, LogRotation, LogSection};
+use std::path::Path;
+use tracing::Subscriber;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// The filter directives, `RUST_LOG` overrides the configured level.
+pub fn filter_directives(log: &LogSection) -> String {
+    std::env::var(EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|directives| !directives.trim().is_empty())
+        .unwrap_or_else(|| log.level.clone())
+}
+
+/// A formatting layer, the JSON lines include the fields of the current span and its parents.
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    }
+}
+
+/// Opens the rotating log file, the rotated files are suffixed with their date.
+fn file_appender(path: &str, rotation: LogRotation) -> RollingFileAppender {
+    let path = Path::new(path);
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let prefix = path
+        .file_name()
+        .map_or_else(|| "eframe_sc2.log".into(), |name| name.to_os_string());
+    let rotation = match rotation {
+        LogRotation::Hourly => Rotation::HOURLY,
+        LogRotation::Daily => Rotation::DAILY,
+        LogRotation::Never => Rotation::NEVER,
+    };
+    RollingFileAppender::new(rotation, dir, prefix)
+}
+
+/// Installs the global subscriber. The returned guard flushes the log file when dropped, it
+/// must be kept until the end of the program.
+pub fn init(log: &LogSection) -> Result<Option<WorkerGuard>, crate::error::Error> {
+    let directives = filter_directives(log);
+    let filter = EnvFilter::try_new(&directives).map_err(|e| {
+        crate::error::Error::Config(format!("Invalid log filter {}: {}", directives, e))
+    })?;
+    let (file_layer, guard) = match &log.file {
+        Some(path) => {
+            let (writer, guard) = tracing_appender::non_blocking(file_appender(path, log.rotation));
+            (Some(fmt_layer(log.format, writer, false)), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer(log.format, std::io::stderr, true))
+        .with(file_layer)
+        .try_init()
+        .map_err(|e| crate::error::Error::Other(e.to_string()))?;
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_directives_are_valid() {
+        let log = LogSection {
+            level: "info,eframe_sc2::query=debug".to_string(),
+            ..Default::default()
+        };
+        assert!(EnvFilter::try_new(filter_directives(&log)).is_ok());
+        assert!(EnvFilter::try_new("info,eframe_sc2=loud").is_err());
+    }
+}
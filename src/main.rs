@@ -2,13 +2,25 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
 #[cfg(not(target_arch = "wasm32"))]
-#[tokio::main]
-async fn main() {
+fn main() {
+    use clap::Parser;
+    let cli = eframe_sc2::cli::Cli::parse();
+    // Before the runtime starts its threads.
+    cli.set_env();
     // This will start the web server.
     // Then we can start the GUI to interact with it.
     // The web server will eventually serve also the wasm to interact
     // with the server as well.
-    eframe_sc2::cli::process_cli_request().await.unwrap();
+    let res = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Unable to start the tokio runtime")
+        .block_on(eframe_sc2::cli::process_cli_request(cli));
+    // The configuration and schema errors span several lines, shown as is.
+    if let Err(e) = res {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
 }
 
 // When compiling to web using trunk:
@@ -2,6 +2,7 @@
 
 use std::time::Instant;
 
+use crate::api::validation::FieldErrors;
 use serde::{Deserialize, Serialize};
 
 /// The status of the response
@@ -11,7 +12,16 @@ pub enum ResponseStatus {
     /// The response was successful
     Ok,
     /// There was an error
-    Error { message: String },
+    Error {
+        /// A human readable description of the error
+        message: String,
+        /// A stable machine readable code, i.e. `not_found`
+        #[serde(default)]
+        code: String,
+        /// The validation messages by field name
+        #[serde(default, skip_serializing_if = "FieldErrors::is_empty")]
+        fields: FieldErrors,
+    },
 }
 
 impl Default for ResponseStatus {
@@ -92,13 +102,28 @@ impl ResponseMetaBuilder {
     }
 
     /// Sets the total number of rows in the data frame
-    pub fn with_error(mut self, error: impl ToString) -> Self {
+    pub fn with_error(self, error: impl ToString) -> Self {
+        self.with_error_code("internal", error)
+    }
+
+    /// Sets the error status with a machine readable code
+    pub fn with_error_code(mut self, code: impl ToString, error: impl ToString) -> Self {
         self.status = ResponseStatus::Error {
             message: error.to_string(),
+            code: code.to_string(),
+            fields: FieldErrors::new(),
         };
         self
     }
 
+    /// Sets the field level validation messages of the error status
+    pub fn with_field_errors(mut self, errors: FieldErrors) -> Self {
+        if let ResponseStatus::Error { fields, .. } = &mut self.status {
+            *fields = errors;
+        }
+        self
+    }
+
     /// Builds the ResponseMeta
     pub fn build(self) -> ResponseMeta {
         let duration = self.start.elapsed().as_millis() as u64;
@@ -0,0 +1,312 @@
+//! Prometheus metrics of the server
+//!
+//! The requests are counted and timed per route by the `track_requests` middleware, the query
+//! durations and the cache hits are recorded by the `QueryRunner` and the `ResponseCache`. The
+//! snapshot row counts and age are computed when `/metrics` is scraped, the row counts are
+//! kept until the snapshot changes.
+
+use crate::server::AppState;
+use crate::store::Table;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+/// The upper bounds of the duration buckets, in seconds
+pub const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A histogram of durations in seconds
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Histogram {
+    /// The number of observations per bucket, not cumulative
+    buckets: [u64; DURATION_BUCKETS.len()],
+    /// The sum of the observations
+    sum: f64,
+    /// The number of observations
+    count: u64,
+}
+
+impl Histogram {
+    /// Records a duration
+    pub fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.buckets[bucket] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    /// Writes the `_bucket`, `_sum` and `_count` series, `labels` are prepended to `le`.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (le, count) in DURATION_BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{{labels}{sep}le=\"{le}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let braces = |labels: &str| {
+            if labels.is_empty() {
+                String::new()
+            } else {
+                format!("{{{labels}}}")
+            }
+        };
+        let _ = writeln!(out, "{name}_sum{} {}", braces(labels), self.sum);
+        let _ = writeln!(out, "{name}_count{} {}", braces(labels), self.count);
+    }
+}
+
+/// Escapes a label value
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The request metrics and the snapshot row counts
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// The number of responses per route, method and status
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    /// The duration of the requests per route
+    latencies: Mutex<BTreeMap<String, Histogram>>,
+    /// The row counts per table and the snapshot version they were counted for
+    snapshot_rows: Mutex<Option<(String, Vec<(Table, u64)>)>>,
+}
+
+impl Metrics {
+    /// Records a response
+    pub fn record_request(&self, route: &str, method: &str, status: u16, seconds: f64) {
+        *self
+            .requests
+            .lock()
+            .unwrap()
+            .entry((route.to_string(), method.to_string(), status))
+            .or_default() += 1;
+        self.latencies
+            .lock()
+            .unwrap()
+            .entry(route.to_string())
+            .or_default()
+            .observe(seconds);
+    }
+
+    /// Writes the request series
+    fn render_requests(&self, out: &mut String) {
+        out.push_str("# HELP sc2_http_requests_total The number of HTTP responses.\n");
+        out.push_str("# TYPE sc2_http_requests_total counter\n");
+        for ((route, method, status), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "sc2_http_requests_total{{route=\"{}\",method=\"{}\",status=\"{}\"}} {}",
+                label(route),
+                label(method),
+                status,
+                count
+            );
+        }
+        out.push_str(
+            "# HELP sc2_http_request_duration_seconds The duration of the HTTP requests.\n",
+        );
+        out.push_str("# TYPE sc2_http_request_duration_seconds histogram\n");
+        for (route, histogram) in self.latencies.lock().unwrap().iter() {
+            histogram.render(
+                out,
+                "sc2_http_request_duration_seconds",
+                &format!("route=\"{}\"", label(route)),
+            );
+        }
+    }
+
+    /// The row count of each table, counted once per snapshot version. The tables that can
+    /// not be counted are left out, i.e. the optional tables missing from the snapshot.
+    async fn snapshot_rows(&self, state: &AppState, version: &str) -> Vec<(Table, u64)> {
+        if let Some((counted, rows)) = self.snapshot_rows.lock().unwrap().as_ref() {
+            if counted == version {
+                return rows.clone();
+            }
+        }
+        let mut rows = vec![];
+        // A busy or timed out count is retried on the next scrape.
+        let mut complete = true;
+        for table in Table::all() {
+            match count_rows(state, *table).await {
+                Ok(Some(count)) => rows.push((*table, count)),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::debug!("Unable to count the rows of {}: {}", table, e);
+                    complete &= e.code() != crate::error::code::UNAVAILABLE;
+                }
+            }
+        }
+        if complete {
+            *self.snapshot_rows.lock().unwrap() = Some((version.to_string(), rows.clone()));
+        }
+        rows
+    }
+}
+
+/// Counts the rows of a table, the scan of a missing file only fails when it is collected.
+async fn count_rows(state: &AppState, table: Table) -> Result<Option<u64>, crate::error::Error> {
+    let scan = state.store.scan(table)?;
+    let count = state
+        .queries
+        .collect(scan.select([len().cast(DataType::UInt64).alias("rows")]))
+        .await?;
+    Ok(count.column("rows")?.u64()?.get(0))
+}
+
+/// Middleware counting and timing the requests per route, the route is the matched path so
+/// that the path parameters do not create new series.
+pub async fn track_requests(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = req.method().to_string();
+    let started = Instant::now();
+    let response = next.run(req).await;
+    state.metrics.record_request(
+        &route,
+        &method,
+        response.status().as_u16(),
+        started.elapsed().as_secs_f64(),
+    );
+    response
+}
+
+/// Renders all the metrics in the Prometheus text format
+pub async fn render(state: &AppState) -> Result<String, crate::error::Error> {
+    // Counted first, the count queries are part of the query durations.
+    let version = crate::cache::snapshot_version(state);
+    let snapshot_rows = state.metrics.snapshot_rows(state, &version).await;
+
+    let mut out = String::new();
+    state.metrics.render_requests(&mut out);
+
+    out.push_str(
+        "# HELP sc2_query_duration_seconds The duration of the completed Polars queries.\n",
+    );
+    out.push_str("# TYPE sc2_query_duration_seconds histogram\n");
+    state
+        .queries
+        .durations()
+        .render(&mut out, "sc2_query_duration_seconds", "");
+    out.push_str("# HELP sc2_query_timeouts_total The number of queries that timed out.\n");
+    out.push_str("# TYPE sc2_query_timeouts_total counter\n");
+    let _ = writeln!(out, "sc2_query_timeouts_total {}", state.queries.timeouts());
+
+    let (hits, misses) = state.cache.hits_and_misses();
+    out.push_str("# HELP sc2_cache_requests_total The cache lookups by result.\n");
+    out.push_str("# TYPE sc2_cache_requests_total counter\n");
+    let _ = writeln!(out, "sc2_cache_requests_total{{result=\"hit\"}} {}", hits);
+    let _ = writeln!(
+        out,
+        "sc2_cache_requests_total{{result=\"miss\"}} {}",
+        misses
+    );
+    out.push_str("# HELP sc2_cache_entries The number of cached responses.\n");
+    out.push_str("# TYPE sc2_cache_entries gauge\n");
+    let _ = writeln!(out, "sc2_cache_entries {}", state.cache.len());
+
+    out.push_str("# HELP sc2_snapshot_rows The number of rows of each snapshot table.\n");
+    out.push_str("# TYPE sc2_snapshot_rows gauge\n");
+    for (table, rows) in snapshot_rows {
+        let _ = writeln!(out, "sc2_snapshot_rows{{table=\"{}\"}} {}", table, rows);
+    }
+    if let Ok(modified) = state.store.date_modified() {
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default()
+            .as_secs_f64();
+        out.push_str("# HELP sc2_snapshot_age_seconds The time since the snapshot was written.\n");
+        out.push_str("# TYPE sc2_snapshot_age_seconds gauge\n");
+        let _ = writeln!(out, "sc2_snapshot_age_seconds {}", age);
+    }
+    Ok(out)
+}
+
+/// Serves the metrics to Prometheus
+pub async fn route_metrics(State(state): State<AppState>) -> Result<Response, crate::error::Error> {
+    let body = render(&state).await?;
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{MemoryStore, ReplayStore};
+    use std::sync::Arc;
+
+    /// A snapshot without `init_data`, its scan fails on collect like a missing IPC file.
+    struct MissingInitData(MemoryStore);
+
+    impl ReplayStore for MissingInitData {
+        fn scan(&self, table: Table) -> Result<LazyFrame, crate::error::Error> {
+            match table {
+                Table::InitData => Ok(DataFrame::empty().lazy().select([col("m_user_id")])),
+                _ => self.0.scan(table),
+            }
+        }
+
+        fn table_modified(&self, table: Table) -> Result<SystemTime, crate::error::Error> {
+            self.0.table_modified(table)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_metrics() {
+        let details = df!("ext_fs_id" => [1u64, 1, 2]).unwrap();
+        let state = AppState::new(
+            "",
+            Arc::new(MissingInitData(
+                MemoryStore::new().with_table(Table::Details, details),
+            )),
+        );
+        state
+            .metrics
+            .record_request("/api/v1/details/maps", "GET", 200, 0.02);
+        state
+            .metrics
+            .record_request("/api/v1/details/maps", "GET", 200, 3.0);
+        let out = render(&state).await.unwrap();
+        assert!(out.contains(
+            "sc2_http_requests_total{route=\"/api/v1/details/maps\",method=\"GET\",status=\"200\"} 2"
+        ));
+        assert!(out.contains(
+            "sc2_http_request_duration_seconds_bucket{route=\"/api/v1/details/maps\",le=\"0.025\"} 1"
+        ));
+        assert!(out.contains(
+            "sc2_http_request_duration_seconds_bucket{route=\"/api/v1/details/maps\",le=\"+Inf\"} 2"
+        ));
+        assert!(out.contains("sc2_snapshot_rows{table=\"details\"} 3"));
+        assert!(!out.contains("sc2_snapshot_rows{table=\"init_data\"}"));
+        // The row count query is recorded.
+        assert!(out.contains("sc2_query_duration_seconds_count 1"));
+    }
+}
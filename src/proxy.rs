@@ -0,0 +1,147 @@
+//! The HTTP `CONNECT` proxy
+//!
+//! The server binds all the interfaces by default, so the proxy is disabled unless enabled in
+//! the configuration. When enabled the tunnels either go to a fixed upstream, i.e. the trunk
+//! dev server, or to the targets of the allowlist. Everything else is logged and rejected.
+
+use crate::config::ProxySection;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    response::{IntoResponse, Response},
+};
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+
+/// Whether an allowlist entry, `host:port` or `host:*`, matches the target.
+fn allows(entry: &str, host: &str, port: &str) -> bool {
+    let Some((allowed_host, allowed_port)) = entry.rsplit_once(':') else {
+        return false;
+    };
+    allowed_host.eq_ignore_ascii_case(host) && (allowed_port == "*" || allowed_port == port)
+}
+
+impl ProxySection {
+    /// The address to tunnel a `CONNECT` to, the reason it is rejected otherwise.
+    pub fn target(&self, authority: &str) -> Result<String, String> {
+        if !self.enabled {
+            return Err("the proxy is disabled".to_string());
+        }
+        if let Some(upstream) = &self.upstream {
+            return Ok(upstream.clone());
+        }
+        let Some((host, port)) = authority.rsplit_once(':') else {
+            return Err("the target has no port".to_string());
+        };
+        if self.allow.iter().any(|entry| allows(entry, host, port)) {
+            Ok(authority.to_string())
+        } else {
+            Err("the target is not in the allowlist".to_string())
+        }
+    }
+}
+
+// From axum/examples/http-proxy/src/main.rs
+/// Tunnels the `CONNECT` request when the configuration allows it, `403` otherwise.
+/// The tunnel is closed when the server shuts down.
+pub async fn proxy(
+    req: Request<Body>,
+    config: &ProxySection,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<Response, hyper::Error> {
+    tracing::trace!(?req);
+
+    let Some(authority) = req.uri().authority().map(|auth| auth.to_string()) else {
+        tracing::warn!("CONNECT host is not socket addr: {:?}", req.uri());
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            "CONNECT must be to a socket address",
+        )
+            .into_response());
+    };
+    let target = match config.target(&authority) {
+        Ok(target) => target,
+        Err(reason) => {
+            tracing::warn!("Rejected CONNECT to {}: {}", authority, reason);
+            return Ok((
+                StatusCode::FORBIDDEN,
+                format!("CONNECT rejected, {}", reason),
+            )
+                .into_response());
+        }
+    };
+    tracing::info!("Tunneling CONNECT to {} through {}", authority, target);
+    let tunneling = async move {
+        match hyper::upgrade::on(req).await {
+            Ok(upgraded) => {
+                if let Err(e) = tunnel(upgraded, target).await {
+                    tracing::warn!("server io error: {}", e);
+                };
+            }
+            Err(e) => tracing::warn!("upgrade error: {}", e),
+        }
+    };
+    tokio::task::spawn(async move {
+        tokio::select! {
+            _ = tunneling => {}
+            _ = shutdown.wait_for(|stop| *stop) => {
+                tracing::debug!("Closing the tunnel to {} on shutdown", authority);
+            }
+        }
+    });
+
+    Ok(Response::new(Body::empty()))
+}
+
+// From axum/examples/http-proxy/src/main.rs
+async fn tunnel(upgraded: Upgraded, addr: String) -> std::io::Result<()> {
+    let mut server = TcpStream::connect(addr).await?;
+    let mut upgraded = TokioIo::new(upgraded);
+
+    let (from_client, from_server) =
+        tokio::io::copy_bidirectional(&mut upgraded, &mut server).await?;
+
+    tracing::debug!(
+        "client wrote {} bytes and received {} bytes",
+        from_client,
+        from_server
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_target() {
+        let disabled = ProxySection::default();
+        assert!(disabled.target("127.0.0.1:8080").is_err());
+
+        let allowlist = ProxySection {
+            enabled: true,
+            allow: vec!["127.0.0.1:8080".to_string(), "Localhost:*".to_string()],
+            upstream: None,
+        };
+        assert_eq!(
+            allowlist.target("127.0.0.1:8080"),
+            Ok("127.0.0.1:8080".to_string())
+        );
+        assert!(allowlist.target("localhost:22").is_ok());
+        assert!(allowlist.target("127.0.0.1:22").is_err());
+        assert!(allowlist.target("example.com:443").is_err());
+
+        let upstream = ProxySection {
+            enabled: true,
+            allow: vec![],
+            upstream: Some("127.0.0.1:8080".to_string()),
+        };
+        assert_eq!(
+            upstream.target("example.com:443"),
+            Ok("127.0.0.1:8080".to_string())
+        );
+    }
+}
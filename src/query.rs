@@ -0,0 +1,160 @@
+//! Runs the Polars queries off the async runtime, bounded in time and in number.
+//!
+//! A query is cancelled when it times out or when the request future is dropped, i.e. the
+//! client disconnected, so that an abandoned aggregation does not keep the CPU busy.
+
+use crate::metrics::Histogram;
+use polars::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// The limits of the queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryLimits {
+    /// The maximum time a query may wait for a slot and run
+    pub timeout: Duration,
+    /// The maximum number of queries running at the same time
+    pub max_concurrent: usize,
+    /// The maximum number of rows returned by the details queries
+    pub max_rows: u32,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_concurrent: 4,
+            max_rows: 10000,
+        }
+    }
+}
+
+/// Cancels the query when dropped before completion.
+struct CancelOnDrop(Option<Arc<InProcessQuery>>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(query) = self.0.take() {
+            tracing::debug!("Cancelling query");
+            query.cancel();
+        }
+    }
+}
+
+/// Collects the LazyFrames within the limits
+#[derive(Debug)]
+pub struct QueryRunner {
+    /// The limits of the queries
+    pub limits: QueryLimits,
+    permits: Arc<Semaphore>,
+    /// The durations of the completed queries
+    durations: Mutex<Histogram>,
+    /// The number of queries that timed out
+    timeouts: AtomicU64,
+}
+
+impl Default for QueryRunner {
+    fn default() -> Self {
+        Self::new(QueryLimits::default())
+    }
+}
+
+impl QueryRunner {
+    /// Creates a new QueryRunner
+    pub fn new(limits: QueryLimits) -> Self {
+        Self {
+            limits,
+            permits: Arc::new(Semaphore::new(limits.max_concurrent.max(1))),
+            durations: Default::default(),
+            timeouts: Default::default(),
+        }
+    }
+
+    /// The durations of the completed queries
+    pub fn durations(&self) -> Histogram {
+        self.durations.lock().unwrap().clone()
+    }
+
+    /// The number of queries that timed out
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+
+    /// Collects the query on a blocking thread. Returns `Error::Unavailable` when no slot is
+    /// free or the query does not complete in time.
+    pub async fn collect(&self, query: LazyFrame) -> Result<DataFrame, crate::error::Error> {
+        let timeout = self.limits.timeout;
+        // The wait for a slot and the run share the deadline.
+        let deadline = tokio::time::Instant::now() + timeout;
+        let timed_out =
+            || crate::error::Error::Unavailable(format!("Query timed out after {:?}", timeout));
+        let permit = tokio::time::timeout_at(deadline, self.permits.clone().acquire_owned())
+            .await
+            .map_err(|_| {
+                crate::error::Error::Unavailable("Too many queries running, try again".to_string())
+            })?
+            .map_err(|e| crate::error::Error::Other(e.to_string()))?;
+        let started = Instant::now();
+        let running = Arc::new(query.collect_concurrently()?);
+        let mut guard = CancelOnDrop(Some(running.clone()));
+        let fetch = tokio::task::spawn_blocking(move || {
+            // The slot is released once the query stops, even after a time out.
+            let _permit = permit;
+            running.fetch_blocking()
+        });
+        match tokio::time::timeout_at(deadline, fetch).await {
+            Err(_) => {
+                self.timeouts.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    duration_ms = started.elapsed().as_millis() as u64,
+                    "Query timed out"
+                );
+                Err(timed_out())
+            }
+            Ok(Err(e)) if e.is_panic() => {
+                guard.0 = None;
+                Err(crate::error::Error::Other(format!("Query panicked: {}", e)))
+            }
+            Ok(Err(e)) => Err(crate::error::Error::Other(format!("Query aborted: {}", e))),
+            Ok(Ok(res)) => {
+                guard.0 = None;
+                let res = res?;
+                self.durations
+                    .lock()
+                    .unwrap()
+                    .observe(started.elapsed().as_secs_f64());
+                // Within the request span, so the slow queries can be traced to their endpoint.
+                tracing::info!(
+                    duration_ms = started.elapsed().as_millis() as u64,
+                    rows = res.height(),
+                    "Query completed"
+                );
+                Ok(res)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_runner_collect() {
+        let runner = QueryRunner::new(QueryLimits {
+            timeout: Duration::from_secs(10),
+            max_concurrent: 1,
+            ..Default::default()
+        });
+        let df = df!("a" => [1u32, 2, 3]).unwrap();
+        let res = runner
+            .collect(df.lazy().filter(col("a").gt(lit(1u32))))
+            .await
+            .unwrap();
+        assert_eq!(res.height(), 2);
+        // The slot is released for the next query.
+        assert_eq!(runner.permits.available_permits(), 1);
+    }
+}
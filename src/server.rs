@@ -1,23 +1,30 @@
-//! HTTP server, routes and proxy
+//! HTTP server and routes, the CONNECT requests go to `proxy`
 
+use crate::api::v1::details::players::identity::PlayerAliases;
+use crate::cache::{CacheConfig, ResponseCache};
+use crate::query::{QueryLimits, QueryRunner};
 use axum::{
     body::Body,
-    extract::ws::{WebSocket, WebSocketUpgrade},
-    http::{Method, Request, StatusCode},
-    response::{IntoResponse, Response},
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    http::{Method, Request},
+    response::Response,
     routing::get,
     routing::Router,
 };
 use hyper::body::Incoming;
 use hyper::server::conn::http1;
-use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
 use std::net::SocketAddr;
-use tokio::net::TcpStream;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 use tower::Service;
 use tower::ServiceExt;
-use tower_http::services::ServeDir;
 use tower_http::trace::{self, TraceLayer};
+use tower_http::LatencyUnit;
 use tracing::Level;
 
 /// Basic state to share through the routes
@@ -25,36 +32,224 @@ use tracing::Level;
 pub struct AppState {
     /// The path to the IPC files
     pub source_dir: String,
+    /// The store providing the snapshot tables
+    pub store: Arc<dyn crate::store::ReplayStore>,
+    /// The user defined player aliases
+    pub aliases: Arc<PlayerAliases>,
+    /// The cache of the API responses
+    pub cache: Arc<ResponseCache>,
+    /// Runs the queries within the time and concurrency limits
+    pub queries: Arc<QueryRunner>,
+    /// The optional parts of the application
+    pub features: crate::config::FeatureSection,
+    /// The request metrics
+    pub metrics: Arc<crate::metrics::Metrics>,
 }
 
-pub async fn start_server(cli: &crate::cli::Cli) {
-    let port = cli.port;
-    let ip = cli.ip.clone();
-    let source_dir = cli.source_dir.clone();
+impl AppState {
+    /// Creates a new AppState without aliases
+    pub fn new(source_dir: impl Into<String>, store: Arc<dyn crate::store::ReplayStore>) -> Self {
+        Self {
+            source_dir: source_dir.into(),
+            store,
+            aliases: Default::default(),
+            cache: Default::default(),
+            queries: Default::default(),
+            features: Default::default(),
+            metrics: Default::default(),
+        }
+    }
+
+    /// Creates the AppState from the configuration, the store, the limits, the features and
+    /// the player aliases.
+    pub fn from_config(config: &crate::config::Config) -> Result<Self, crate::error::Error> {
+        let source_dir = config.source_dir()?;
+        let store = crate::store::open_store(source_dir, config.snapshot.store_format);
+        let mut state = Self::new(source_dir, store)
+            .with_cache(config.cache_config())
+            .with_query_limits(config.query_limits());
+        state.features = config.features.clone();
+        if let Some(aliases_path) = &config.snapshot.aliases {
+            match PlayerAliases::load(aliases_path) {
+                Ok(aliases) => {
+                    tracing::info!(
+                        "Loaded {} player aliases from {}",
+                        aliases.0.len(),
+                        aliases_path
+                    );
+                    state = state.with_aliases(aliases);
+                }
+                Err(e) => {
+                    tracing::error!("Unable to load the player aliases {}: {}", aliases_path, e)
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    /// Sets the player aliases
+    pub fn with_aliases(mut self, aliases: PlayerAliases) -> Self {
+        self.aliases = Arc::new(aliases);
+        self
+    }
+
+    /// Replaces the response cache with an empty one with the limits
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Arc::new(ResponseCache::new(config));
+        self
+    }
+
+    /// Replaces the query runner with one with the limits
+    pub fn with_query_limits(mut self, limits: QueryLimits) -> Self {
+        self.queries = Arc::new(QueryRunner::new(limits));
+        self
+    }
+}
+
+/// A running server, see `spawn_server`. The server also stops when the handle is dropped.
+pub struct ServerHandle {
+    /// The address the server listens on
+    pub local_addr: SocketAddr,
+    /// Set to true to stop the server
+    shutdown: watch::Sender<bool>,
+    /// The accept loop
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ServerHandle {
+    /// Stops accepting connections and waits for the in-flight ones, at most the grace period.
+    /// The WebSockets and the CONNECT tunnels are closed right away.
+    pub async fn shutdown(self) {
+        tracing::info!("Stopping server on {}", self.local_addr);
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("Server task failed: {}", e);
+        }
+        tracing::info!("Server stopped");
+    }
+}
+
+/// Completes on Ctrl-C, or on SIGTERM on unix.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = signal::ctrl_c().await {
+            tracing::error!("Unable to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Unable to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl-C"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+/// Binds the listener and serves the requests on a background task.
+pub async fn spawn_server(
+    config: &crate::config::Config,
+) -> Result<ServerHandle, crate::error::Error> {
+    let port = config.server.port;
+    let ip = config.server.ip.clone();
     tracing::info!("Starting server on {}:{}", ip, port);
     // Start a backend thread to serve requests
-    let shared_state = AppState { source_dir };
+    let shared_state = AppState::from_config(config)?;
+    // Fails fast rather than on the first query.
+    crate::health::ensure_schema(&shared_state)?;
+    let snapshot = shared_state.source_dir.clone();
+    // Also closes the WebSockets and the CONNECT tunnels, they are detached from the
+    // connections once upgraded.
+    let (shutdown, mut shutdown_rx) = watch::channel(false);
+    let ws_shutdown = shutdown_rx.clone();
+    let next_request_id = Arc::new(AtomicU64::new(1));
     let router_svc = Router::new()
-        .layer(
-            TraceLayer::new_for_http()
-                .make_span_with(trace::DefaultMakeSpan::new().level(Level::INFO))
-                .on_response(trace::DefaultOnResponse::new().level(Level::INFO)),
-        )
         .with_state(shared_state.clone())
         .nest(
             "/api",
-            crate::api::routes(axum::extract::State(shared_state.clone())),
+            crate::api::routes(axum::extract::State(shared_state.clone()))
+                .layer(axum::middleware::from_fn_with_state(
+                    shared_state.clone(),
+                    crate::cache::cache_responses,
+                ))
+                // Outside of the cache so that the cached responses are counted too.
+                .layer(axum::middleware::from_fn_with_state(
+                    shared_state.clone(),
+                    crate::metrics::track_requests,
+                )),
+        )
+        .route(
+            "/metrics",
+            get(crate::metrics::route_metrics).with_state(shared_state.clone()),
+        )
+        .route("/healthz", get(crate::health::route_healthz))
+        .route(
+            "/readyz",
+            get(crate::health::route_readyz).with_state(shared_state.clone()),
         )
-        .route("/_trunk/ws", get(web_socket_handler))
-        .nest_service("/", ServeDir::new("dist"));
+        .route(
+            "/_trunk/ws",
+            get(move |ws: WebSocketUpgrade| web_socket_handler(ws, ws_shutdown.clone())),
+        )
+        .fallback_service(
+            crate::assets::StaticAssets::new(config.server.static_dir.as_deref()).service(),
+        )
+        // Last so that it wraps all the routes, the events of a request carry its span fields.
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(move |req: &Request<Body>| {
+                    let request_id = req
+                        .headers()
+                        .get("x-request-id")
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| {
+                            next_request_id.fetch_add(1, Ordering::Relaxed).to_string()
+                        });
+                    tracing::info_span!(
+                        "request",
+                        request_id = %request_id,
+                        method = %req.method(),
+                        endpoint = %req.uri().path(),
+                        snapshot = %snapshot,
+                    )
+                })
+                .on_response(
+                    trace::DefaultOnResponse::new()
+                        .level(Level::INFO)
+                        .latency_unit(LatencyUnit::Millis),
+                ),
+        );
 
+    if config.proxy.enabled {
+        tracing::warn!(
+            "CONNECT proxy enabled, upstream: {:?}, allowed: {:?}",
+            config.proxy.upstream,
+            config.proxy.allow
+        );
+    }
+    let proxy_config = Arc::new(config.proxy.clone());
+    let proxy_shutdown = shutdown_rx.clone();
     // From axum/examples/http-proxy/src/main.rs
     let tower_service = tower::service_fn(move |req: Request<_>| {
         let router_svc = router_svc.clone();
+        let proxy_config = proxy_config.clone();
+        let proxy_shutdown = proxy_shutdown.clone();
         let req = req.map(Body::new);
         async move {
             if req.method() == Method::CONNECT {
-                proxy(req).await
+                crate::proxy::proxy(req, &proxy_config, proxy_shutdown).await
             } else {
                 router_svc.oneshot(req).await.map_err(|err| match err {})
             }
@@ -67,35 +262,91 @@ pub async fn start_server(cli: &crate::cli::Cli) {
 
     let addr: SocketAddr = format!("{}:{}", ip, port)
         .parse()
-        .expect("Invalid IP address");
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    tracing::debug!("listening on {}", listener.local_addr().unwrap());
-
-    loop {
-        let (stream, _) = listener.accept().await.unwrap();
-        let io = TokioIo::new(stream);
-        let hyper_service = hyper_service.clone();
-        tokio::task::spawn(async move {
-            if let Err(err) = http1::Builder::new()
-                .preserve_header_case(true)
-                .title_case_headers(true)
-                .serve_connection(io, hyper_service)
-                .with_upgrades()
-                .await
-            {
-                println!("Failed to serve connection: {:?}", err);
+        .map_err(|e| crate::error::Error::Other(format!("Invalid IP address {}: {}", ip, e)))?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    tracing::debug!("listening on {}", local_addr);
+    let grace = Duration::from_secs(config.server.shutdown_grace);
+    // Accepts the connections until the shutdown is requested, then waits for the open
+    // connections to complete for the grace period before closing them.
+    let task = tokio::spawn(async move {
+        let mut connections = JoinSet::new();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let io = TokioIo::new(stream);
+                        let hyper_service = hyper_service.clone();
+                        let mut shutdown = shutdown_rx.clone();
+                        connections.spawn(async move {
+                            let conn = http1::Builder::new()
+                                .preserve_header_case(true)
+                                .title_case_headers(true)
+                                .serve_connection(io, hyper_service)
+                                .with_upgrades();
+                            tokio::pin!(conn);
+                            let mut draining = false;
+                            loop {
+                                tokio::select! {
+                                    res = conn.as_mut() => {
+                                        if let Err(err) = res {
+                                            tracing::debug!("Failed to serve connection: {:?}", err);
+                                        }
+                                        break;
+                                    }
+                                    _ = shutdown.changed(), if !draining => {
+                                        draining = true;
+                                        conn.as_mut().graceful_shutdown();
+                                    }
+                                }
+                            }
+                        });
+                    }
+                    // i.e. too many open files, the next connections may succeed.
+                    Err(e) => {
+                        tracing::warn!("Unable to accept connection: {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                },
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                _ = shutdown_rx.changed() => break,
             }
-        });
-    }
+        }
+        drop(listener);
+        tracing::info!("Waiting for {} open connections", connections.len());
+        let drained = tokio::time::timeout(grace, async {
+            while connections.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!(
+                "Closing {} connections after the grace period",
+                connections.len()
+            );
+            connections.abort_all();
+        }
+    });
+    Ok(ServerHandle {
+        local_addr,
+        shutdown,
+        task,
+    })
 }
 
-async fn web_socket_handler(ws: WebSocketUpgrade) -> Response {
-    ws.on_upgrade(handle_socket)
+async fn web_socket_handler(ws: WebSocketUpgrade, shutdown: watch::Receiver<bool>) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, shutdown))
 }
 
-async fn handle_socket(mut socket: WebSocket) {
-    while let Some(msg) = socket.recv().await {
-        let msg = if let Ok(msg) = msg {
+async fn handle_socket(mut socket: WebSocket, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        let msg = tokio::select! {
+            msg = socket.recv() => msg,
+            _ = shutdown.wait_for(|stop| *stop) => {
+                let _ = socket.send(Message::Close(None)).await;
+                return;
+            }
+        };
+        let msg = if let Some(Ok(msg)) = msg {
             msg
         } else {
             // client disconnected
@@ -108,46 +359,3 @@ async fn handle_socket(mut socket: WebSocket) {
         }
     }
 }
-
-async fn proxy(req: Request<Body>) -> Result<Response, hyper::Error> {
-    tracing::trace!(?req);
-
-    if let Some(host_addr) = req.uri().authority().map(|auth| auth.to_string()) {
-        tokio::task::spawn(async move {
-            match hyper::upgrade::on(req).await {
-                Ok(upgraded) => {
-                    if let Err(e) = tunnel(upgraded, host_addr).await {
-                        tracing::warn!("server io error: {}", e);
-                    };
-                }
-                Err(e) => tracing::warn!("upgrade error: {}", e),
-            }
-        });
-
-        Ok(Response::new(axum::body::Body::empty()))
-    } else {
-        tracing::warn!("CONNECT host is not socket addr: {:?}", req.uri());
-        Ok((
-            StatusCode::BAD_REQUEST,
-            "CONNECT must be to a socket address",
-        )
-            .into_response())
-    }
-}
-
-// From axum/examples/http-proxy/src/main.rs
-async fn tunnel(upgraded: Upgraded, addr: String) -> std::io::Result<()> {
-    let mut server = TcpStream::connect(addr).await?;
-    let mut upgraded = TokioIo::new(upgraded);
-
-    let (from_client, from_server) =
-        tokio::io::copy_bidirectional(&mut upgraded, &mut server).await?;
-
-    tracing::debug!(
-        "client wrote {} bytes and received {} bytes",
-        from_client,
-        from_server
-    );
-
-    Ok(())
-}
@@ -0,0 +1,271 @@
+//! Replay snapshot storage backends
+//!
+//! The queries do not care where the snapshot tables live, they only need a
+//! `LazyFrame` per logical table. The [`ReplayStore`] trait provides that.
+
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// The logical tables available in a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Table {
+    /// The replay details, one row per player per replay.
+    Details,
+    /// The replay init data, contains the lobby slots.
+    InitData,
+    /// The UnitBorn tracker events.
+    UnitBorn,
+    /// The UnitDied tracker events.
+    UnitDied,
+    /// The PlayerStats tracker events.
+    Stats,
+    /// The Upgrade tracker events.
+    Upgrades,
+}
+
+impl Table {
+    /// The file name of the table without the extension
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            Self::Details => "details",
+            Self::InitData => "init_data",
+            Self::UnitBorn => "unit_born",
+            Self::UnitDied => "unit_died",
+            Self::Stats => "stats",
+            Self::Upgrades => "upgrades",
+        }
+    }
+
+    /// All the tables known to the stores.
+    pub fn all() -> &'static [Table] {
+        &[
+            Self::Details,
+            Self::InitData,
+            Self::UnitBorn,
+            Self::UnitDied,
+            Self::Stats,
+            Self::Upgrades,
+        ]
+    }
+}
+
+impl std::fmt::Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file_stem())
+    }
+}
+
+/// The on-disk format of a snapshot directory.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreFormat {
+    /// Arrow IPC files, as written by s2protocol `write-arrow-ipc`
+    #[default]
+    Ipc,
+    /// Parquet files, either a single file per table or a directory per table.
+    Parquet,
+}
+
+/// A source of snapshot tables.
+pub trait ReplayStore: Send + Sync {
+    /// Returns a LazyFrame to query the table.
+    fn scan(&self, table: Table) -> Result<LazyFrame, crate::error::Error>;
+
+    /// Returns the time at which the table was last written.
+    fn table_modified(&self, table: Table) -> Result<SystemTime, crate::error::Error>;
+
+    /// Returns the time at which the snapshot was last written, the `details` table is
+    /// written last.
+    fn date_modified(&self) -> Result<SystemTime, crate::error::Error> {
+        self.table_modified(Table::Details)
+    }
+}
+
+/// Creates the store for the requested format on the source directory.
+pub fn open_store(source_dir: &str, format: StoreFormat) -> Arc<dyn ReplayStore> {
+    match format {
+        StoreFormat::Ipc => Arc::new(IpcStore::new(source_dir)),
+        StoreFormat::Parquet => Arc::new(ParquetStore::new(source_dir)),
+    }
+}
+
+/// A directory of Arrow IPC files, i.e. `details.ipc`
+#[derive(Debug, Clone)]
+pub struct IpcStore {
+    /// The path to the IPC files
+    pub source_dir: String,
+}
+
+impl IpcStore {
+    /// Creates a new IpcStore
+    pub fn new(source_dir: impl Into<String>) -> Self {
+        Self {
+            source_dir: source_dir.into(),
+        }
+    }
+}
+
+impl ReplayStore for IpcStore {
+    fn scan(&self, table: Table) -> Result<LazyFrame, crate::error::Error> {
+        Ok(LazyFrame::scan_ipc(
+            format!("{}/{}.ipc", self.source_dir, table.file_stem()),
+            Default::default(),
+        )?)
+    }
+
+    fn table_modified(&self, table: Table) -> Result<SystemTime, crate::error::Error> {
+        let ipc_filename = format!("{}/{}.ipc", self.source_dir, table.file_stem());
+        Ok(std::fs::metadata(ipc_filename)?.modified()?)
+    }
+}
+
+/// A directory of Parquet files.
+/// Each table can be a single `details.parquet` file or a `details/` directory
+/// containing several parquet files, for example from compressed snapshots.
+#[derive(Debug, Clone)]
+pub struct ParquetStore {
+    /// The path to the parquet files
+    pub source_dir: String,
+}
+
+impl ParquetStore {
+    /// Creates a new ParquetStore
+    pub fn new(source_dir: impl Into<String>) -> Self {
+        Self {
+            source_dir: source_dir.into(),
+        }
+    }
+}
+
+impl ReplayStore for ParquetStore {
+    fn scan(&self, table: Table) -> Result<LazyFrame, crate::error::Error> {
+        let table_dir = format!("{}/{}", self.source_dir, table.file_stem());
+        let path = if Path::new(&table_dir).is_dir() {
+            format!("{}/*.parquet", table_dir)
+        } else {
+            format!("{}.parquet", table_dir)
+        };
+        Ok(LazyFrame::scan_parquet(path, Default::default())?)
+    }
+
+    fn table_modified(&self, table: Table) -> Result<SystemTime, crate::error::Error> {
+        let table_dir = format!("{}/{}", self.source_dir, table.file_stem());
+        let path = if Path::new(&table_dir).is_dir() {
+            table_dir
+        } else {
+            format!("{}.parquet", table_dir)
+        };
+        Ok(std::fs::metadata(path)?.modified()?)
+    }
+}
+
+/// Keeps the tables in memory, useful for tests that need to fill the tables directly.
+#[derive(Debug, Clone)]
+pub struct MemoryStore {
+    /// The tables by their logical name.
+    pub tables: HashMap<Table, DataFrame>,
+    /// The time the store was created, used as the snapshot date.
+    pub created_at: SystemTime,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            tables: HashMap::new(),
+            created_at: SystemTime::now(),
+        }
+    }
+}
+
+impl MemoryStore {
+    /// Creates a new empty MemoryStore
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the contents of a table
+    pub fn with_table(mut self, table: Table, df: DataFrame) -> Self {
+        self.tables.insert(table, df);
+        self
+    }
+}
+
+impl ReplayStore for MemoryStore {
+    fn scan(&self, table: Table) -> Result<LazyFrame, crate::error::Error> {
+        match self.tables.get(&table) {
+            Some(df) => Ok(df.clone().lazy()),
+            None => Err(crate::error::Error::NotFound(format!(
+                "Table {} not loaded in memory store",
+                table
+            ))),
+        }
+    }
+
+    fn table_modified(&self, table: Table) -> Result<SystemTime, crate::error::Error> {
+        if self.tables.contains_key(&table) {
+            Ok(self.created_at)
+        } else {
+            Err(crate::error::Error::NotFound(format!(
+                "Table {} not loaded in memory store",
+                table
+            )))
+        }
+    }
+}
+
+/// Fixtures shared by the tests of the queries.
+#[cfg(test)]
+pub mod test_support {
+    use super::*;
+
+    /// Creates the server state over an in-memory `details` table.
+    /// The `ext_datetime` column, if any, is given in milliseconds since the epoch.
+    pub fn details_state(details: DataFrame) -> crate::server::AppState {
+        let details = if details.column("ext_datetime").is_ok() {
+            details
+                .lazy()
+                .with_column(
+                    col("ext_datetime").cast(DataType::Datetime(TimeUnit::Milliseconds, None)),
+                )
+                .collect()
+                .unwrap()
+        } else {
+            details
+        };
+        crate::server::AppState::new(
+            "",
+            Arc::new(MemoryStore::new().with_table(Table::Details, details)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store_scan() {
+        let df = df!(
+            "title" => ["Alcyone LE", "Emerald City LE"],
+            "ext_fs_id" => [1u64, 2u64],
+        )
+        .unwrap();
+        let store = MemoryStore::new().with_table(Table::Details, df);
+        let res = store.scan(Table::Details).unwrap().collect().unwrap();
+        assert_eq!(res.height(), 2);
+        assert!(store.scan(Table::UnitBorn).is_err());
+    }
+}